@@ -0,0 +1,213 @@
+//! Benchmarks on generated maps, run with `cargo bench`. All maps are generated from a fixed seed, so every run measures the same work.
+//! The maps have 100 and 1000 obstacles, set JKM_BENCH_LARGE=1 to measure 10000 as well.
+//! `cargo bench -- --save <file>` stores the results, `cargo bench -- --compare <file>` prints them next to the stored ones.
+
+extern crate jkm_shortest_path_map;
+
+use std::env;
+use std::fs::File;
+use std::io::{Read, Write};
+use std::time::Instant;
+
+use jkm_shortest_path_map::JkmShortestPathMap;
+
+const SEED: u64 = 0x6a6b_6d5f_7370_6d31;
+const CELL: f64 = 10.0;
+const QUERIES: usize = 1000;
+const SAMPLES: usize = 100;
+
+// xorshift64*, good enough to scatter obstacles
+struct Rng(u64);
+
+impl Rng {
+	fn next(&mut self) -> u64 {
+		self.0 ^= self.0 >> 12;
+		self.0 ^= self.0 << 25;
+		self.0 ^= self.0 >> 27;
+		self.0.wrapping_mul(0x2545_f491_4f6c_dd1d)
+	}
+
+	fn below(&mut self, n: u64) -> u64 {
+		self.next() % n
+	}
+
+	fn range(&mut self, low: f64, high: f64) -> f64 {
+		low + (high - low) * (self.next() >> 11) as f64 / (1u64 << 53) as f64
+	}
+}
+
+type Generator = fn(&mut Rng, usize) -> Scene;
+
+struct Scene {
+	map: (f64, f64, f64, f64),
+	start: (f64, f64),
+	end: (f64, f64),
+	obstacles: Vec<(f64, f64, f64, f64)>,
+}
+
+// Square map with one obstacle per cell, start and end in opposite corners.
+// The map is a bit larger than the cells, obstacles that stick out of it can break insert_obstacle().
+fn scene(n: usize, start: (f64, f64), obstacles: Vec<(f64, f64, f64, f64)>) -> Scene {
+	let side = (n as f64).sqrt().ceil() * CELL + 2.0;
+	Scene { map: (0.0, 0.0, side, side), start, end: (side - start.0, side - start.1), obstacles }
+}
+
+fn cells(n: usize) -> Vec<(f64, f64)> {
+	let side = (n as f64).sqrt().ceil() as usize;
+	(0..n).map(|i| ((i % side) as f64 * CELL, (i / side) as f64 * CELL)).collect()
+}
+
+// Long shelves in rows, with aisles between them
+fn warehouse(rng: &mut Rng, n: usize) -> Scene {
+	let obstacles = cells(n).into_iter().map(|(x, y)| (x + 1.0, y + 2.0, (4 + rng.below(5)) as f64, 3.0)).collect();
+	scene(n, (0.5, 0.5), obstacles)
+}
+
+// Blocks of different size, separated by streets that are one to three units wide
+fn city(rng: &mut Rng, n: usize) -> Scene {
+	let mut street = || (1 + rng.below(3)) as f64;
+	let obstacles = cells(n).into_iter().map(|(x, y)| {
+		let (left, top) = (street(), street());
+		(x + left, y + top, CELL - left - street(), CELL - top - street())
+	}).collect();
+	scene(n, (0.5, 0.5), obstacles)
+}
+
+// One wall on the top or left side of each cell, neighbouring walls overlap
+fn maze(rng: &mut Rng, n: usize) -> Scene {
+	let obstacles = cells(n).into_iter().map(|(x, y)| {
+		if rng.below(2) == 0 { (x, y, CELL + 1.0, 1.0) } else { (x, y, 1.0, CELL + 1.0) }
+	}).collect();
+	scene(n, (CELL / 2.0, CELL / 2.0), obstacles)
+}
+
+fn build(scene: &Scene) -> JkmShortestPathMap {
+	let mut spm = JkmShortestPathMap::new(scene.start, scene.end, scene.map);
+	spm.add_map_border();
+	spm
+}
+
+fn random_point(rng: &mut Rng, spm: &JkmShortestPathMap, (mx, my, mw, mh): (f64, f64, f64, f64)) -> (f64, f64) {
+	loop {
+		let (x, y) = (rng.range(mx, mx + mw), rng.range(my, my + mh));
+		if spm.region_id(x, y).is_some() { return (x, y); }
+	}
+}
+
+// Microseconds per call of f, which is called the given number of times
+fn time<F: FnMut(usize)>(calls: usize, mut f: F) -> f64 {
+	let started = Instant::now();
+	for i in 0..calls {
+		f(i);
+	}
+	let elapsed = started.elapsed();
+	(elapsed.as_secs() as f64 * 1e6 + f64::from(elapsed.subsec_nanos()) / 1e3) / calls as f64
+}
+
+// Results as (operation, microseconds per call)
+fn measure(rng: &mut Rng, scene: &Scene, churn: bool) -> Vec<(&'static str, f64)> {
+	let mut results = Vec::new();
+	// only the empty map with its border, the obstacles are measured by insert_obstacle
+	results.push(("new_empty", time(QUERIES, |_| { build(scene); })));
+
+	let mut spm = build(scene);
+	results.push(("insert_obstacle", time(scene.obstacles.len(), |i| {
+		let (x, y, w, h) = scene.obstacles[i];
+		spm.insert_obstacle(x, y, w, h);
+	})));
+
+	let points: Vec<(f64, f64)> = (0..QUERIES).map(|_| random_point(rng, &spm, scene.map)).collect();
+	let mut checkpoints = Vec::new();
+	results.push(("nearest_checkpoint", time(QUERIES, |i| {
+		if let Some(checkpoint) = spm.nearest_checkpoint(points[i].0, points[i].1) { checkpoints.push(checkpoint); }
+	})));
+	if !checkpoints.is_empty() {
+		results.push(("next_checkpoint", time(checkpoints.len(), |i| { spm.next_checkpoint(checkpoints[i].0, checkpoints[i].1); })));
+	}
+
+	if churn {
+		let extra: Vec<(f64, f64, f64, f64)> = (0..SAMPLES).map(|_| {
+			let (x, y) = random_point(rng, &spm, scene.map);
+			(x, y, rng.range(1.0, 3.0), rng.range(1.0, 3.0))
+		}).collect();
+		results.push(("insert_and_remove", time(SAMPLES, |i| {
+			let (x, y, w, h) = extra[i];
+			spm.insert_obstacle(x, y, w, h);
+			spm.remove_obstacle(x, y, w, h);
+		})));
+	}
+
+	// without replacement, an obstacle that has already been removed would make remove_obstacle() do nothing
+	let samples = SAMPLES.min(scene.obstacles.len());
+	let mut order: Vec<usize> = (0..scene.obstacles.len()).collect();
+	for i in 0..samples {
+		let j = i + rng.below((order.len() - i) as u64) as usize;
+		order.swap(i, j);
+	}
+	let removed: Vec<(f64, f64, f64, f64)> = order[..samples].iter().map(|&i| scene.obstacles[i]).collect();
+	results.push(("remove_obstacle", time(samples, |i| {
+		let (x, y, w, h) = removed[i];
+		spm.remove_obstacle(x, y, w, h);
+	})));
+	results
+}
+
+// Lines of "workload obstacles operation microseconds"
+fn load(path: &str) -> Vec<(String, f64)> {
+	let mut text = String::new();
+	match File::open(path) {
+		Ok(mut file) => { file.read_to_string(&mut text).expect("The baseline is not readable."); }
+		Err(e) => panic!("Can not open the baseline {}: {}", path, e),
+	}
+	text.lines().filter_map(|line| {
+		let mut words: Vec<&str> = line.split_whitespace().collect();
+		let value = words.pop().and_then(|w| w.parse().ok());
+		value.map(|v| (words.join(" "), v))
+	}).collect()
+}
+
+fn main() {
+	let args: Vec<String> = env::args().collect();
+	let option = |name: &str| args.iter().position(|a| a == name).and_then(|i| args.get(i + 1)).cloned();
+	let baseline = option("--compare").map(|path| load(&path));
+	let large = env::var("JKM_BENCH_LARGE").map(|v| v == "1").unwrap_or(false);
+	let sizes: &[usize] = if large { &[100, 1000, 10000] } else { &[100, 1000] };
+	let workloads: [(&str, Generator, bool); 4] = [
+		("warehouse", warehouse, false),
+		("city", city, false),
+		("maze", maze, false),
+		("churn", city, true),
+	];
+
+	let mut lines = Vec::new();
+	match baseline {
+		Some(_) => println!("{:<10} {:>9} {:<20} {:>12} {:>12} {:>8}", "workload", "obstacles", "operation", "before [us]", "after [us]", "change"),
+		None => println!("{:<10} {:>9} {:<20} {:>12}", "workload", "obstacles", "operation", "time [us]"),
+	}
+	for &(name, generate, churn) in workloads.iter() {
+		for &n in sizes.iter() {
+			let mut rng = Rng(SEED ^ n as u64);
+			let scene = generate(&mut rng, n);
+			for (operation, micros) in measure(&mut rng, &scene, churn) {
+				let key = format!("{} {} {}", name, n, operation);
+				let before = baseline.as_ref().and_then(|b| b.iter().find(|(k, _)| *k == key).map(|&(_, v)| v));
+				match (&baseline, before) {
+					(&Some(_), Some(before)) => println!("{:<10} {:>9} {:<20} {:>12.3} {:>12.3} {:>+7.1}%", name, n, operation, before, micros, (micros / before - 1.0) * 100.0),
+					(&Some(_), None) => println!("{:<10} {:>9} {:<20} {:>12} {:>12.3} {:>8}", name, n, operation, "-", micros, "-"),
+					_ => println!("{:<10} {:>9} {:<20} {:>12.3}", name, n, operation, micros),
+				}
+				lines.push(format!("{} {}", key, micros));
+			}
+		}
+	}
+	println!("new_empty builds the map with its border but without obstacles, which are timed by insert_obstacle.");
+	if !large {
+		println!("Maps with 10000 obstacles are left out, set JKM_BENCH_LARGE=1 to measure them.");
+	}
+	if let Some(path) = option("--save") {
+		let mut file = File::create(&path).expect("Can not create the file for the results.");
+		for line in lines.iter() {
+			writeln!(file, "{}", line).expect("Can not write the results.");
+		}
+	}
+}
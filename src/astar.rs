@@ -0,0 +1,121 @@
+//! Paths between two arbitrary coordinates, found with A* over the graph with both coordinates added as temporary nodes
+
+use std::collections::{BinaryHeap, HashMap};
+
+use super::{JkmShortestPathMap, Scalar, MinSortableNode};
+use hierarchy::Path;
+use overlay::Overlay;
+
+// Cost and previous node of all nodes that a search has reached
+type Explored<S> = (HashMap<usize, S>, HashMap<usize, usize>);
+
+impl<S: Scalar> JkmShortestPathMap<S> {
+
+	/// Returns the shortest path between two arbitrary coordinates on the map, the destination of the map is not involved.
+	/// Both coordinates are added to the graph as temporary nodes, like the start and the destination,
+	/// and it is searched with A* and a manhattan heuristic.
+	/// If there are portals, the heuristic is not used because a jump can be shorter than the manhattan distance.
+	/// The map itself is not changed.
+	/// Returns None if there is no path or one of the coordinates is outside of the map or blocked.
+	pub fn find_path(&self, a: (S, S), b: (S, S)) -> Option<Path<S>> {
+		self.search(a, b, None)
+	}
+
+	// A* between the two coordinates, edges with less than the minimum clearance are skipped
+	pub(crate) fn search(&self, a: (S, S), b: (S, S), min_clearance: Option<S>) -> Option<Path<S>> {
+		self.search_within(a, &[b], None, min_clearance).pop().and_then(|path| path)
+	}
+
+	// Shortest paths from one coordinate to each of the targets, None for the targets that can not be reached.
+	// With an area (x, y, w, h), the paths do not leave it.
+	pub(crate) fn search_within(&self, a: (S, S), targets: &[(S, S)], area: Option<(S,S,S,S)>, min_clearance: Option<S>) -> Vec<Option<Path<S>>> {
+		let area = area.unwrap_or(self.map);
+		if !self.is_free_within(a, area) { return vec![None; targets.len()]; }
+		let mut overlay = Overlay::new(self);
+		let start = overlay.add_point(self, a);
+		let ends: Vec<Option<usize>> = targets.iter().map(|&b| if self.is_free_within(b, area) { Some(overlay.add_point(self, b)) } else { None }).collect();
+		let guide = match targets {
+			[b] if self.portals.is_empty() => Some(*b),
+			_ => None,
+		};
+		let (costs, previous) = self.explore(&overlay, start, &ends, guide, area, min_clearance);
+
+		ends.iter().zip(targets.iter()).map(|(&end, &b)| {
+			let end = end?;
+			let cost = *costs.get(&end)?;
+			let mut points = vec![b];
+			let mut n = end;
+			while let Some(&p) = previous.get(&n) {
+				points.push(overlay.position(self, p));
+				n = p;
+			}
+			if points.len() == 1 { points.push(a); }
+			points.reverse();
+			points.dedup();
+			Some(Path { points, cost })
+		}).collect()
+	}
+
+	// Costs of the shortest paths between all pairs of the coordinates that do not leave the area, the cost from the i-th
+	// to the j-th coordinate is at i * n + j, n being the number of coordinates. All coordinates share the same temporary nodes.
+	pub(crate) fn costs_within(&self, points: &[(S, S)], area: (S,S,S,S)) -> Vec<S> {
+		let mut overlay = Overlay::new(self);
+		let nodes: Vec<Option<usize>> = points.iter().map(|&p| if self.is_free_within(p, area) { Some(overlay.add_point(self, p)) } else { None }).collect();
+		let mut costs = Vec::with_capacity(points.len() * points.len());
+		for &start in nodes.iter() {
+			match start {
+				Some(start) => {
+					let (reached, _) = self.explore(&overlay, start, &nodes, None, area, None);
+					costs.extend(nodes.iter().map(|end| end.and_then(|end| reached.get(&end).cloned()).unwrap_or(S::INFINITY)));
+				}
+				None => costs.extend(nodes.iter().map(|_| S::INFINITY)),
+			}
+		}
+		costs
+	}
+
+	// Searches from the start until all ends have been reached, without leaving the area.
+	// With a guide, the search is directed towards that coordinate.
+	fn explore(&self, overlay: &Overlay<S>, start: usize, ends: &[Option<usize>], guide: Option<(S, S)>, area: (S,S,S,S), min_clearance: Option<S>) -> Explored<S> {
+		let heuristic = |(x, y): (S, S)| guide.map_or(S::ZERO, |b| (x - b.0).abs() + (y - b.1).abs());
+		let mut open: Vec<usize> = ends.iter().filter_map(|&end| end).collect();
+		open.sort_unstable();
+		open.dedup();
+
+		let mut costs: HashMap<usize, S> = HashMap::new();
+		let mut previous: HashMap<usize, usize> = HashMap::new();
+		let mut queue = BinaryHeap::new();
+		costs.insert(start, S::ZERO);
+		queue.push(MinSortableNode(start, heuristic(overlay.position(self, start))));
+		while let Some(MinSortableNode(n, estimate)) = queue.pop() {
+			let position = overlay.position(self, n);
+			if estimate > costs[&n] + heuristic(position) { continue; }
+			if let Ok(i) = open.binary_search(&n) {
+				open.remove(i);
+				if open.is_empty() { break; }
+			}
+			for (m, step, kind) in overlay.moves(self, n) {
+				let next_position = overlay.position(self, m);
+				if !is_within(next_position, area) { continue; }
+				if min_clearance.is_some_and(|width| overlay.clearance(self, n, m, kind) < width) { continue; }
+				let next = costs[&n] + step;
+				if next < costs.get(&m).cloned().unwrap_or(S::INFINITY) {
+					costs.insert(m, next);
+					previous.insert(m, n);
+					queue.push(MinSortableNode(m, next + heuristic(next_position)));
+				}
+			}
+		}
+		(costs, previous)
+	}
+
+	// Whether a search can start or end on the coordinate
+	fn is_free_within(&self, p: (S, S), area: (S,S,S,S)) -> bool {
+		let (mx, my, mw, mh) = self.map;
+		is_within(p, area) && p.0 >= mx && p.0 <= mx + mw && p.1 >= my && p.1 <= my + mh && !self.coordinate_is_blocked(p.0, p.1)
+	}
+}
+
+fn is_within<S: Scalar>((x, y): (S, S), (ax, ay, aw, ah): (S,S,S,S)) -> bool {
+	x >= ax && x <= ax + aw && y >= ay && y <= ay + ah
+}
@@ -0,0 +1,230 @@
+//! Command line tool to try out maps without writing any code.
+//!
+//! Usage: jkm-spm <map file> [--start x,y] <command> [arguments]
+//!
+//! The map file is either a .jkmmap file, which needs --start because it does not store the start point,
+//! or a description with one entry per line, # starts a comment:
+//!     map x y w h
+//!     start x y
+//!     end x y
+//!     border
+//!     obstacle x y w h
+//!
+//! Commands:
+//!     cost x,y ...     cost of the shortest path from each point to the destination
+//!     path x,y ...     checkpoints from each point to the destination
+//!     next x,y ...     next checkpoint on the way to the destination
+//!     validate         checks the invariants of the graph, exits with 1 if one is violated
+//!     svg <file>       draws the map, the obstacles and the shortest paths
+//!     jkmmap <file>    writes the map for the inspector
+
+extern crate jkm_shortest_path_map;
+
+use std::env;
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::process;
+
+use jkm_shortest_path_map::JkmShortestPathMap;
+
+const USAGE: &str = "Usage: jkm-spm <map file> [--start x,y] <cost|path|next> x,y ...\n       jkm-spm <map file> [--start x,y] validate\n       jkm-spm <map file> [--start x,y] <svg|jkmmap> <output file>";
+
+type Rect = (f64, f64, f64, f64);
+
+// Everything that is needed to build the map and to draw it
+struct Description {
+	map: Rect,
+	start: (f64, f64),
+	end: (f64, f64),
+	border: bool,
+	obstacles: Vec<Rect>,
+}
+
+fn main() {
+	let mut args: Vec<String> = env::args().skip(1).collect();
+	let start = match args.iter().position(|a| a == "--start") {
+		Some(i) if i + 1 < args.len() => {
+			let value = args.remove(i + 1);
+			args.remove(i);
+			Some(parse_point(&value).unwrap_or_else(|e| fail(&e)))
+		}
+		Some(_) => fail("--start needs a point x,y."),
+		None => None,
+	};
+	if args.len() < 2 { fail(USAGE); }
+	let (spm, obstacles) = load(&args[0], start).unwrap_or_else(|e| fail(&e));
+
+	let command = args[1].as_str();
+	let points = || -> Vec<(f64, f64)> {
+		if args.len() < 3 { fail("Give at least one point x,y."); }
+		args[2..].iter().map(|a| parse_point(a).unwrap_or_else(|e| fail(&e))).collect()
+	};
+	match command {
+		"cost" => for p in points() {
+			match path(&spm, p) {
+				Some(path) => println!("{},{}: {}", p.0, p.1, cost(&path)),
+				None => println!("{},{}: unreachable", p.0, p.1),
+			}
+		},
+		"path" => for p in points() {
+			match path(&spm, p) {
+				Some(path) => println!("{},{}: {}", p.0, p.1, path.iter().map(|&(x, y)| format!("{},{}", x, y)).collect::<Vec<_>>().join(" ")),
+				None => println!("{},{}: unreachable", p.0, p.1),
+			}
+		},
+		"next" => for p in points() {
+			match spm.nearest_checkpoint(p.0, p.1) {
+				Some((x, y)) => println!("{},{}: {},{}", p.0, p.1, x, y),
+				None => println!("{},{}: unreachable", p.0, p.1),
+			}
+		},
+		"validate" => match spm.check_invariants() {
+			Ok(()) => println!("ok"),
+			Err(e) => { eprintln!("{}", e); process::exit(1); }
+		},
+		"svg" | "jkmmap" => {
+			let name = args.get(2).unwrap_or_else(|| fail("Give the name of the output file."));
+			let file = File::create(name).unwrap_or_else(|e| fail(&format!("Can not create {}: {}", name, e)));
+			let mut out = BufWriter::new(file);
+			let written = if command == "svg" { write_svg(&spm, &obstacles, &mut out) } else { spm.write_jkmmap(&mut out) };
+			if let Err(e) = written.and_then(|_| out.flush()) {
+				fail(&format!("Can not write {}: {}", name, e));
+			}
+		}
+		_ => fail(USAGE),
+	}
+}
+
+fn fail(message: &str) -> ! {
+	eprintln!("{}", message);
+	process::exit(2);
+}
+
+fn parse_point(text: &str) -> Result<(f64, f64), String> {
+	let numbers: Vec<Option<f64>> = text.split(',').map(|n| n.trim().parse().ok()).collect();
+	match numbers.as_slice() {
+		[Some(x), Some(y)] => Ok((*x, *y)),
+		_ => Err(format!("{} is not a point x,y.", text)),
+	}
+}
+
+// Returns the map together with the obstacles that have been inserted
+fn load(name: &str, start: Option<(f64, f64)>) -> Result<(JkmShortestPathMap, Vec<Rect>), String> {
+	let file = File::open(name).map_err(|e| format!("Can not open {}: {}", name, e))?;
+	if name.ends_with(".jkmmap") {
+		let start = start.ok_or("A .jkmmap file does not store the start point, give it with --start x,y.")?;
+		let spm = JkmShortestPathMap::read_jkmmap(BufReader::new(file), start)?;
+		// the obstacles are drawn from the file again
+		let obstacles = BufReader::new(File::open(name).map_err(|e| e.to_string())?).lines()
+			.map_while(Result::ok)
+			.skip_while(|line| line.trim() != "#")
+			.skip(1)
+			.filter_map(|line| {
+				let n: Vec<f64> = line.split('|').filter_map(|f| f.trim().parse().ok()).collect();
+				if n.len() == 4 { Some((n[0], n[1], n[2], n[3])) } else { None }
+			})
+			.collect();
+		return Ok((spm, obstacles));
+	}
+	let mut description = parse_description(BufReader::new(file))?;
+	if let Some(start) = start { description.start = start; }
+	let d = &description;
+	let (mx, my, mw, mh) = d.map;
+	for &(x, y) in [d.start, d.end].iter() {
+		if x < mx || x > mx + mw || y < my || y > my + mh { return Err(format!("The point {},{} is not on the map.", x, y)); }
+	}
+	if d.start == d.end { return Err("The start and the end have to be different.".to_string()); }
+	let mut spm = JkmShortestPathMap::new(d.start, d.end, d.map);
+	if d.border { spm.add_map_border(); }
+	for &(x, y, w, h) in d.obstacles.iter() {
+		spm.insert_obstacle(x, y, w, h);
+	}
+	Ok((spm, description.obstacles))
+}
+
+fn parse_description<R: BufRead>(input: R) -> Result<Description, String> {
+	let (mut map, mut start, mut end) = (None, None, None);
+	let mut border = false;
+	let mut obstacles = Vec::new();
+	for (number, line) in input.lines().enumerate() {
+		let line = line.map_err(|e| e.to_string())?;
+		let line = line.split('#').next().unwrap_or("").trim();
+		if line.is_empty() { continue; }
+		let mut words = line.split_whitespace();
+		let keyword = words.next().unwrap_or("");
+		let numbers: Vec<f64> = words.map(|w| w.parse().map_err(|_| format!("Line {}: {} is not a number.", number + 1, w)))
+			.collect::<Result<_, _>>()?;
+		let expected = match keyword { "map" | "obstacle" => 4, "start" | "end" => 2, "border" => 0, _ => return Err(format!("Line {}: unknown entry {}.", number + 1, keyword)) };
+		if numbers.len() != expected {
+			return Err(format!("Line {}: {} needs {} numbers.", number + 1, keyword, expected));
+		}
+		match keyword {
+			"map" => map = Some((numbers[0], numbers[1], numbers[2], numbers[3])),
+			"start" => start = Some((numbers[0], numbers[1])),
+			"end" => end = Some((numbers[0], numbers[1])),
+			"border" => border = true,
+			_ => obstacles.push((numbers[0], numbers[1], numbers[2], numbers[3])),
+		}
+	}
+	Ok(Description {
+		map: map.ok_or("The map rectangle is missing.")?,
+		start: start.ok_or("The start point is missing.")?,
+		end: end.ok_or("The end point is missing.")?,
+		border,
+		obstacles,
+	})
+}
+
+// Checkpoints from the point to the destination, including both of them
+fn path(spm: &JkmShortestPathMap, from: (f64, f64)) -> Option<Vec<(f64, f64)>> {
+	let destination = spm.get_destination_coordinates();
+	let mut points = vec![from];
+	let mut current = spm.nearest_checkpoint(from.0, from.1)?;
+	let nodes = spm.snapshot().node_count();
+	loop {
+		if current != *points.last().unwrap() { points.push(current); }
+		if current == destination { return Some(points); }
+		// a path can not visit more checkpoints than there are nodes
+		if points.len() > nodes + 1 { return None; }
+		current = spm.next_checkpoint(current.0, current.1)?;
+	}
+}
+
+fn cost(path: &[(f64, f64)]) -> f64 {
+	path.windows(2).map(|w| (w[0].0 - w[1].0).abs() + (w[0].1 - w[1].1).abs()).sum()
+}
+
+fn write_svg<W: Write>(spm: &JkmShortestPathMap, obstacles: &[Rect], out: &mut W) -> std::io::Result<()> {
+	let snapshot = spm.snapshot();
+	let positions: Vec<(f64, f64)> = (0..snapshot.node_count()).map(|n| snapshot.node_position(n)).collect();
+	let (mut left, mut top, mut right, mut bottom) = (positions[0].0, positions[0].1, positions[0].0, positions[0].1);
+	for &(x, y) in positions.iter() {
+		left = left.min(x);
+		top = top.min(y);
+		right = right.max(x);
+		bottom = bottom.max(y);
+	}
+	let margin = (right - left).max(bottom - top) / 20.0;
+	writeln!(out, r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="{} {} {} {}">"#, left - margin, top - margin, right - left + 2.0 * margin, bottom - top + 2.0 * margin)?;
+	let stroke = margin / 10.0;
+	writeln!(out, r#"<rect x="{}" y="{}" width="{}" height="{}" fill="white" stroke="black" stroke-width="{}"/>"#, left, top, right - left, bottom - top, stroke)?;
+	for &(x, y, w, h) in obstacles.iter() {
+		writeln!(out, r#"<rect x="{}" y="{}" width="{}" height="{}" fill="gray" fill-opacity="0.7"/>"#, x, y, w, h)?;
+	}
+	// edges, then the shortest paths on top of them
+	for (n, &(x, y)) in positions.iter().enumerate() {
+		for neighbour in snapshot.node_neighbours(n).iter().take(2).filter_map(|&m| m) {
+			let (nx, ny) = positions[neighbour];
+			writeln!(out, r#"<line x1="{}" y1="{}" x2="{}" y2="{}" stroke="lightblue" stroke-width="{}"/>"#, x, y, nx, ny, stroke)?;
+		}
+	}
+	for (n, &(x, y)) in positions.iter().enumerate() {
+		if let Some(next) = snapshot.next_node(n) {
+			let (nx, ny) = positions[next];
+			writeln!(out, r#"<line x1="{}" y1="{}" x2="{}" y2="{}" stroke="blue" stroke-width="{}"/>"#, x, y, nx, ny, stroke * 2.0)?;
+		}
+	}
+	let (ex, ey) = spm.get_destination_coordinates();
+	writeln!(out, r#"<circle cx="{}" cy="{}" r="{}" fill="red"/>"#, ex, ey, stroke * 5.0)?;
+	writeln!(out, "</svg>")
+}
@@ -0,0 +1,66 @@
+//! Blocking the border of the map, each side separately
+
+use super::{JkmShortestPathMap, Scalar};
+
+/// Thickness of the border added by add_map_border(), for integer maps it is one unit
+pub const DEFAULT_BORDER_THICKNESS: f64 = 0.0625;
+
+/// Whether paths can leave the map on one side, see JkmShortestPathMap::set_border()
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum BorderMode<S = f64> {
+	/// Obstacles that stick out of the map on this side can be passed outside of the map
+	Open,
+	/// The side is blocked by an obstacle of the given thickness just outside of the map
+	Closed(S),
+}
+
+impl<S: Scalar> JkmShortestPathMap<S> {
+
+	/// Opens or closes each side of the map. A closed side is an obstacle outside of the map along the whole side,
+	/// it cuts the paths that went around obstacles sticking out of the map there and the shortest paths are updated immediately.
+	/// Paths can still go along the border itself.
+	///
+	/// #Panics
+	/// Panics if the thickness of a closed side is not positive.
+	pub fn set_border(&mut self, north: BorderMode<S>, east: BorderMode<S>, south: BorderMode<S>, west: BorderMode<S>) {
+		let modes = [north, east, south, west];
+		for &mode in modes.iter() {
+			if let BorderMode::Closed(thickness) = mode {
+				if thickness.is_nan() || thickness <= S::ZERO { panic!("The thickness of the border has to be positive, got {}.", thickness); }
+			}
+		}
+		if self.border == modes { return; }
+		for (side, &mode) in modes.iter().enumerate() {
+			if self.border[side] == mode { continue; }
+			if let BorderMode::Closed(thickness) = self.border[side] {
+				self.disable_blocker(border_blocker(self.map, side, thickness));
+			}
+			self.border[side] = mode;
+			if let BorderMode::Closed(thickness) = mode {
+				// the blocker lies outside of the map, only edges around obstacles that stick out can go through it
+				let blocker = border_blocker(self.map, side, thickness);
+				let blocked = self.blocked_edges(blocker);
+				self.push_obstacle(blocker);
+				self.cut_edges(&blocked);
+				self.seal_touching(blocker);
+			}
+		}
+		self.after_update();
+	}
+
+	/// Returns the modes of the sides in the order north, east, south, west
+	pub fn border(&self) -> [BorderMode<S>; 4] {
+		self.border
+	}
+}
+
+// The obstacle that closes a side of the map, sides are numbered like directions
+fn border_blocker<S: Scalar>((x,y,w,h): (S,S,S,S), side: usize, thickness: S) -> (S,S,S,S) {
+	let d = thickness;
+	match side {
+		0 => (x, y-d, w, d),
+		1 => (x+w, y, d, h),
+		2 => (x, y+h, w, d),
+		_ => (x-d, y, d, h),
+	}
+}
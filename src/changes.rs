@@ -0,0 +1,79 @@
+//! Reporting which shortest paths have changed, so that agents only have to plan again if their path is affected.
+//! The nodes are recorded by position whenever their path is changed, because node indices change when the graph is shrunk.
+
+use std::cmp::Ordering;
+use std::mem;
+
+use super::{JkmShortestPathMap, Scalar};
+
+/// Nodes whose shortest path has changed, see JkmShortestPathMap::take_path_changes()
+#[derive(Clone, PartialEq, Debug)]
+pub struct PathChanges<S = f64> {
+	/// Positions of the nodes whose cost or next step changed, including nodes that have been added or erased
+	pub nodes: Vec<(S, S)>,
+	/// Smallest rectangle (x, y, w, h) that contains all of these nodes, None if nothing changed
+	pub area: Option<(S,S,S,S)>,
+}
+
+impl<S: Scalar> PathChanges<S> {
+	/// Whether no shortest path has changed
+	pub fn is_empty(&self) -> bool {
+		self.nodes.is_empty()
+	}
+
+	/// Whether the coordinate lies within the area of the changes, including its border
+	pub fn affects(&self, x: S, y: S) -> bool {
+		match self.area {
+			Some((ax, ay, aw, ah)) => x >= ax && x <= ax + aw && y >= ay && y <= ay + ah,
+			None => false,
+		}
+	}
+}
+
+impl<S: Scalar> JkmShortestPathMap<S> {
+
+	/// Starts recording which shortest paths change, they are collected until take_path_changes() is called.
+	/// Calling it again while recording discards the changes collected so far.
+	pub fn track_path_changes(&mut self) {
+		self.tracked_paths = Some(Vec::new());
+	}
+
+	/// Stops recording changes of shortest paths
+	pub fn untrack_path_changes(&mut self) {
+		self.tracked_paths = None;
+	}
+
+	/// Returns the nodes whose shortest path has been computed again since track_path_changes() or the last call of this function,
+	/// this includes all nodes whose cost or next step changed and nodes that have been added or erased.
+	/// Agents whose cached checkpoint is one of these nodes, or who are inside of the area of the changes, should plan again.
+	/// Returns None if track_path_changes() has not been called.
+	pub fn take_path_changes(&mut self) -> Option<PathChanges<S>> {
+		let mut nodes = self.tracked_paths.as_mut().map(mem::take)?;
+		nodes.sort_by(compare_positions);
+		nodes.dedup();
+		let area = nodes.first().map(|&first| {
+			let (mut left, mut top, mut right, mut bottom) = (first.0, first.1, first.0, first.1);
+			for &(x, y) in nodes.iter() {
+				left = left.min(x);
+				top = top.min(y);
+				right = right.max(x);
+				bottom = bottom.max(y);
+			}
+			(left, top, right - left, bottom - top)
+		});
+		Some(PathChanges { nodes, area })
+	}
+
+	// Records the position of the node if path changes are tracked, called whenever its shortest path is changed or invalidated
+	pub(crate) fn note_path_change(&mut self, n: usize) {
+		if let Some(ref mut nodes) = self.tracked_paths {
+			let (x, y) = (self.graph[n].x, self.graph[n].y);
+			if x != S::NEG_INFINITY { nodes.push((x, y)); }
+		}
+	}
+}
+
+fn compare_positions<S: Scalar>(a: &(S, S), b: &(S, S)) -> Ordering {
+	a.0.partial_cmp(&b.0).unwrap_or(Ordering::Equal)
+		.then(a.1.partial_cmp(&b.1).unwrap_or(Ordering::Equal))
+}
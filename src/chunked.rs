@@ -0,0 +1,414 @@
+//! A world that is too large to be held in one map. It is split into square chunks that can be loaded and unloaded with their obstacles.
+//! Paths between chunks go over entrances on the chunk borders. The costs between the entrances of a chunk are kept
+//! after it is unloaded, therefore queries across unloaded chunks still see the obstacles inside of them.
+
+use std::cmp;
+use std::collections::{BinaryHeap, HashMap};
+
+use super::{JkmShortestPathMap, Scalar, MinSortableNode};
+use hierarchy::Path;
+
+/// Space around each chunk that belongs to its local graph, so that the entrances are not on the border of a map
+const CHUNK_MARGIN: f64 = 1.0 / 64.0;
+
+/// The world, see the module documentation
+#[derive(Clone, Debug)]
+pub struct ChunkedWorld<S = f64> {
+	grid: Grid<S>,
+	chunks: HashMap<(i64, i64), Chunk<S>>,
+}
+
+#[derive(Clone, Debug)]
+struct Chunk<S> {
+	obstacles: Vec<(S,S,S,S)>,
+	// local graph of the chunk with its obstacles, None while the chunk is unloaded
+	map: Option<JkmShortestPathMap<S>>,
+	// costs[a * n + b] is the cost from entrance a to entrance b, n being the number of entrances of the chunk.
+	// They are only computed again when the obstacles of the chunk change.
+	costs: Vec<S>,
+}
+
+/// Entrance on the border between two chunks: (vertical border, chunk x, chunk y, index on the border).
+/// A vertical border is the west side of the chunk, a horizontal one its north side.
+pub(crate) type Entrance = (bool, i64, i64, usize);
+
+/// Square cells with evenly spaced entrances on their borders, the chunks of a ChunkedWorld or the clusters of a map
+#[derive(Copy, Clone, Debug)]
+pub(crate) struct Grid<S> {
+	pub(crate) size: S,
+	pub(crate) entrances_per_side: usize,
+}
+
+impl<S: Scalar> ChunkedWorld<S> {
+
+	/// Creates an empty world of square chunks with the given side length. Each side of a chunk has the given number of
+	/// entrances, evenly spaced along it. Paths from one chunk to the next can only go through these entrances,
+	/// more entrances make the costs more accurate and the queries slower.
+	///
+	/// #Panics
+	/// Panics if the chunk size is not positive or if there are no entrances.
+	pub fn new(chunk_size: S, entrances_per_side: usize) -> ChunkedWorld<S> {
+		if chunk_size.is_nan() || chunk_size <= S::ZERO { panic!("The chunk size has to be positive, got {}.", chunk_size); }
+		if entrances_per_side == 0 { panic!("Chunks need at least one entrance per side."); }
+		ChunkedWorld {
+			grid: Grid::new(chunk_size, entrances_per_side),
+			chunks: HashMap::new(),
+		}
+	}
+
+	/// Returns the chunk (x, y) that contains the coordinate, coordinates on a border belong to the chunk on their east or south
+	pub fn chunk_at(&self, x: S, y: S) -> (i64, i64) {
+		self.grid.cell_at(x, y)
+	}
+
+	/// Checks whether the local graph of the chunk is in memory
+	pub fn is_loaded(&self, chunk: (i64, i64)) -> bool {
+		match self.chunks.get(&chunk) {
+			Some(c) => c.map.is_some(),
+			None => false,
+		}
+	}
+
+	/// Loads the chunk with the given obstacles in world coordinates, only their parts inside of the chunk are used.
+	/// A chunk that is already loaded is replaced.
+	pub fn load_chunk(&mut self, chunk: (i64, i64), obstacles: &[(S,S,S,S)]) {
+		let area = self.grid.area(chunk);
+		let obstacles = obstacles.iter().cloned().filter(|&o| rectangles_overlap(o, area)).collect();
+		let mut c = Chunk { obstacles, map: None, costs: Vec::new() };
+		self.build_chunk(chunk, &mut c);
+		self.chunks.insert(chunk, c);
+	}
+
+	/// Drops the local graph and the obstacles of the chunk and returns the obstacles.
+	/// The costs between its entrances are kept for queries that go through the chunk.
+	/// Returns an empty list if the chunk was not loaded.
+	pub fn unload_chunk(&mut self, chunk: (i64, i64)) -> Vec<(S,S,S,S)> {
+		match self.chunks.get_mut(&chunk) {
+			Some(c) => {
+				c.map = None;
+				std::mem::take(&mut c.obstacles)
+			}
+			None => Vec::new(),
+		}
+	}
+
+	/// Adds an obstacle to all chunks it overlaps.
+	///
+	/// #Panics
+	/// Panics if one of these chunks is not loaded.
+	pub fn insert_obstacle(&mut self, x: S, y: S, w: S, h: S) {
+		let obstacle = (x,y,w,h);
+		for chunk in self.grid.cells_overlapping(obstacle) {
+			let mut c = self.loaded_chunk(chunk);
+			c.obstacles.push(obstacle);
+			if let Some(ref mut map) = c.map {
+				let (x,y,w,h) = self.local_obstacle(chunk, obstacle);
+				map.insert_obstacle(x,y,w,h);
+				trim_local_map(map);
+				map.after_update();
+			}
+			self.summarize(chunk, &mut c);
+			self.chunks.insert(chunk, c);
+		}
+	}
+
+	/// Removes an obstacle that was inserted before with the same parameters, either by insert_obstacle() or load_chunk().
+	/// The local graphs of the chunks it overlaps are built again from their remaining obstacles.
+	///
+	/// #Panics
+	/// Panics if one of the chunks it overlaps is not loaded.
+	pub fn remove_obstacle(&mut self, x: S, y: S, w: S, h: S) {
+		let obstacle = (x,y,w,h);
+		for chunk in self.grid.cells_overlapping(obstacle) {
+			let mut c = self.loaded_chunk(chunk);
+			if let Some(i) = c.obstacles.iter().position(|&o| o == obstacle) {
+				c.obstacles.swap_remove(i);
+				// where the obstacle covered the border of the local map, the lines along the border are gone and
+				// remove_obstacle() has nothing to link them to, therefore the local graph is built again
+				self.build_chunk(chunk, &mut c);
+			}
+			self.chunks.insert(chunk, c);
+		}
+	}
+
+	/// Returns the cost of the shortest path between two coordinates, or None if there is none.
+	/// Inside of loaded chunks and across chunks that have been loaded before, the cost is exact for paths that cross chunk borders only at entrances.
+	/// Chunks that have never been loaded are assumed to be free, their cost is the manhattan distance.
+	/// Unloaded chunks that contain one of the coordinates are treated the same way.
+	pub fn path_cost(&self, from: (S, S), to: (S, S)) -> Option<S> {
+		self.search(from, to).map(|(cost, _)| cost)
+	}
+
+	/// Returns the shortest path between two coordinates with the same cost as path_cost().
+	/// The path is first planned over the entrances, then each part of it is refined with the local graph of its chunk.
+	/// Inside of chunks that are not loaded, the path goes straight to the next entrance.
+	pub fn path(&self, from: (S, S), to: (S, S)) -> Option<Path<S>> {
+		let (cost, entrances) = self.search(from, to)?;
+		let mut points = vec![from];
+		if entrances.is_empty() {
+			points.extend(self.leg(self.chunk_at(from.0, from.1), from, to));
+		}
+		else {
+			let (first, last) = (entrances[0], entrances[entrances.len() - 1]);
+			points.extend(self.leg(self.chunk_at(from.0, from.1), from, self.grid.position(first)));
+			for pair in entrances.windows(2) {
+				let chunk = self.grid.common_cell(pair[0], pair[1]);
+				points.extend(self.leg(chunk, self.grid.position(pair[0]), self.grid.position(pair[1])));
+			}
+			points.extend(self.leg(self.chunk_at(to.0, to.1), self.grid.position(last), to));
+		}
+		points.dedup();
+		Some(Path { points, cost })
+	}
+
+	// Plans the way over the entrances, limited to the known chunks and one chunk around them, see plan()
+	fn search(&self, from: (S, S), to: (S, S)) -> Option<(S, Vec<Entrance>)> {
+		let (start_chunk, end_chunk) = (self.chunk_at(from.0, from.1), self.chunk_at(to.0, to.1));
+		let direct = if start_chunk == end_chunk { self.cost_inside(start_chunk, from, to) } else { S::INFINITY };
+		let (mut min, mut max) = (start_chunk, start_chunk);
+		for &(x, y) in self.chunks.keys().chain([end_chunk].iter()) {
+			min = (cmp::min(min.0, x - 1), cmp::min(min.1, y - 1));
+			max = (cmp::max(max.0, x + 1), cmp::max(max.1, y + 1));
+		}
+		let from_start = self.costs_to_entrances(start_chunk, from);
+		let to_end = self.costs_to_entrances(end_chunk, to);
+		plan(&self.grid, (start_chunk, end_chunk), (min, max), direct, &from_start, &to_end, |chunk, a, b| self.entrance_cost(chunk, a, b))
+	}
+
+	// Points from one coordinate in the chunk to another one, without the first coordinate
+	fn leg(&self, chunk: (i64, i64), from: (S, S), to: (S, S)) -> Vec<(S, S)> {
+		match self.chunks.get(&chunk).and_then(|c| c.map.as_ref()).and_then(|map| map.find_path(from, to)) {
+			Some(path) => path.points[1..].to_vec(),
+			None => corner_path(from, to),
+		}
+	}
+
+	// Builds the local graph of a loaded chunk and computes the costs between its entrances
+	fn build_chunk(&self, chunk: (i64, i64), c: &mut Chunk<S>) {
+		let (x, y, w, h) = self.grid.area(chunk);
+		let m = chunk_margin::<S>();
+		let two = S::from_f64(2.0);
+		let mut map = JkmShortestPathMap::new((x + w / two, y + h / two), (x - m, y - m), (x - m, y - m, w + m + m, h + m + m));
+		for &o in c.obstacles.iter() {
+			let (x, y, w, h) = self.local_obstacle(chunk, o);
+			map.insert_obstacle(x, y, w, h);
+		}
+		trim_local_map(&mut map);
+		map.recompute_paths();
+		map.after_update();
+		c.map = Some(map);
+		self.summarize(chunk, c);
+	}
+
+	// Computes the costs between all entrances on the local graph
+	fn summarize(&self, chunk: (i64, i64), c: &mut Chunk<S>) {
+		let map = match c.map { Some(ref map) => map, None => return };
+		let positions: Vec<(S, S)> = self.grid.entrances(chunk).into_iter().map(|e| self.grid.position(e)).collect();
+		c.costs = map.costs_within(&positions, map.map);
+	}
+
+	// Cost from entrance a to entrance b of the chunk
+	fn entrance_cost(&self, chunk: (i64, i64), a: usize, b: usize) -> S {
+		match self.chunks.get(&chunk) {
+			Some(c) => c.costs[a * 4 * self.grid.entrances_per_side + b],
+			None => {
+				let entrances = self.grid.entrances(chunk);
+				manhattan(self.grid.position(entrances[a]), self.grid.position(entrances[b]))
+			}
+		}
+	}
+
+	// Costs from a coordinate inside of the chunk to all of its entrances
+	fn costs_to_entrances(&self, chunk: (i64, i64), p: (S, S)) -> Vec<S> {
+		let entrances = self.grid.entrances(chunk);
+		match self.chunks.get(&chunk) {
+			Some(&Chunk { map: Some(ref map), .. }) => {
+				let positions: Vec<(S, S)> = entrances.into_iter().map(|e| self.grid.position(e)).collect();
+				map.search_within(p, &positions, None, None).into_iter().map(|path| path.map_or(S::INFINITY, |path| path.cost)).collect()
+			}
+			_ => entrances.into_iter().map(|e| manhattan(p, self.grid.position(e))).collect(),
+		}
+	}
+
+	// Cost between two coordinates without leaving their chunk
+	fn cost_inside(&self, chunk: (i64, i64), from: (S, S), to: (S, S)) -> S {
+		if from == to { return S::ZERO; }
+		match self.chunks.get(&chunk) {
+			Some(&Chunk { map: Some(ref map), .. }) => map.find_path(from, to).map_or(S::INFINITY, |path| path.cost),
+			_ => manhattan(from, to),
+		}
+	}
+
+	// Takes a loaded chunk out of the world so that it can be modified while the world is borrowed
+	fn loaded_chunk(&mut self, chunk: (i64, i64)) -> Chunk<S> {
+		if !self.is_loaded(chunk) { panic!("The chunk {:?} is not loaded.", chunk); }
+		self.chunks.remove(&chunk).expect("Loaded chunks are in the world.")
+	}
+
+	// Cuts the obstacle to the chunk. Where it reaches the border of the chunk, it is extended beyond the local map
+	// so that no path can go around it inside of the margin.
+	fn local_obstacle(&self, chunk: (i64, i64), (x, y, w, h): (S,S,S,S)) -> (S,S,S,S) {
+		let (cx, cy, cw, ch) = self.grid.area(chunk);
+		let d = chunk_margin::<S>() + chunk_margin::<S>();
+		let left = if x <= cx { cx - d } else { x };
+		let top = if y <= cy { cy - d } else { y };
+		let right = if x + w >= cx + cw { cx + cw + d } else { x + w };
+		let bottom = if y + h >= cy + ch { cy + ch + d } else { y + h };
+		(left, top, right - left, bottom - top)
+	}
+}
+
+impl<S: Scalar> Grid<S> {
+	pub(crate) fn new(size: S, entrances_per_side: usize) -> Grid<S> {
+		Grid { size, entrances_per_side }
+	}
+
+	// Cell that contains the coordinate, coordinates on a border belong to the cell on their east or south
+	pub(crate) fn cell_at(&self, x: S, y: S) -> (i64, i64) {
+		let size = self.size.to_f64();
+		((x.to_f64() / size).floor() as i64, (y.to_f64() / size).floor() as i64)
+	}
+
+	// Area (x, y, w, h) of the cell
+	pub(crate) fn area(&self, (x, y): (i64, i64)) -> (S,S,S,S) {
+		(S::from_f64(x as f64) * self.size, S::from_f64(y as f64) * self.size, self.size, self.size)
+	}
+
+	// All cells that share an area with the rectangle
+	pub(crate) fn cells_overlapping(&self, rectangle: (S,S,S,S)) -> Vec<(i64, i64)> {
+		let (x, y, w, h) = rectangle;
+		let (min, max) = (self.cell_at(x, y), self.cell_at(x + w, y + h));
+		let mut cells = Vec::new();
+		for cx in min.0..max.0 + 1 {
+			for cy in min.1..max.1 + 1 {
+				if rectangles_overlap(rectangle, self.area((cx, cy))) { cells.push((cx, cy)); }
+			}
+		}
+		cells
+	}
+
+	// Entrances of the cell in the order west, north, east, south
+	pub(crate) fn entrances(&self, (x, y): (i64, i64)) -> Vec<Entrance> {
+		let k = self.entrances_per_side;
+		let mut entrances = Vec::with_capacity(4 * k);
+		entrances.extend((0..k).map(|i| (true, x, y, i)));
+		entrances.extend((0..k).map(|i| (false, x, y, i)));
+		entrances.extend((0..k).map(|i| (true, x + 1, y, i)));
+		entrances.extend((0..k).map(|i| (false, x, y + 1, i)));
+		entrances
+	}
+
+	// A cell that has both entrances on its border
+	pub(crate) fn common_cell(&self, a: Entrance, b: Entrance) -> (i64, i64) {
+		let cells = self.cells_of(b);
+		*self.cells_of(a).iter().find(|cell| cells.contains(cell)).expect("Consecutive entrances share a cell.")
+	}
+
+	// The two cells on both sides of the entrance
+	pub(crate) fn cells_of(&self, (vertical, x, y, _): Entrance) -> [(i64, i64); 2] {
+		if vertical { [(x - 1, y), (x, y)] } else { [(x, y - 1), (x, y)] }
+	}
+
+	pub(crate) fn position(&self, (vertical, x, y, i): Entrance) -> (S, S) {
+		let along = S::from_f64((i as f64 + 0.5) * self.size.to_f64() / self.entrances_per_side as f64);
+		let (x, y) = (S::from_f64(x as f64) * self.size, S::from_f64(y as f64) * self.size);
+		if vertical { (x, y + along) } else { (x + along, y) }
+	}
+}
+
+// Dijkstra over the entrances from the first to the second of the cells, through the cells between min and max of the bounds.
+// `direct` is the cost of the way that stays inside of a single cell, `from_start` and `to_end` are the costs between the coordinates
+// and the entrances of their cells and `inside(cell, a, b)` is the cost from entrance a to entrance b of the cell.
+// Returns the cost and the entrances on the way, which are none if the shortest path stays in one cell.
+pub(crate) fn plan<S: Scalar, F: Fn((i64, i64), usize, usize) -> S>(grid: &Grid<S>, (start_cell, end_cell): ((i64, i64), (i64, i64)), (min, max): ((i64, i64), (i64, i64)),
+	direct: S, from_start: &[S], to_end: &[S], inside: F) -> Option<(S, Vec<Entrance>)> {
+	let in_bounds = |(x, y): (i64, i64)| x >= min.0 && x <= max.0 && y >= min.1 && y <= max.1;
+	let mut best = direct;
+	let mut last = None;
+
+	let mut ids: HashMap<Entrance, usize> = HashMap::new();
+	let mut entrances: Vec<Entrance> = Vec::new();
+	let mut costs: Vec<S> = Vec::new();
+	let mut previous: Vec<Option<usize>> = Vec::new();
+	let mut queue = BinaryHeap::new();
+	for (i, &e) in grid.entrances(start_cell).iter().enumerate() {
+		let cost = from_start[i];
+		if cost < S::INFINITY {
+			let id = entrance_id(&mut ids, &mut entrances, &mut costs, &mut previous, e);
+			costs[id] = cost;
+			queue.push(MinSortableNode(id, cost));
+		}
+	}
+	let end_entrances = grid.entrances(end_cell);
+	while let Some(MinSortableNode(id, cost)) = queue.pop() {
+		if cost > costs[id] || cost >= best { continue; }
+		let e = entrances[id];
+		if let Some(i) = end_entrances.iter().position(|&end| end == e) {
+			let total = cost + to_end[i];
+			if total < best {
+				best = total;
+				last = Some(id);
+			}
+		}
+		for &cell in grid.cells_of(e).iter().filter(|&&cell| in_bounds(cell)) {
+			let cell_entrances = grid.entrances(cell);
+			let a = cell_entrances.iter().position(|&other| other == e).expect("An entrance is on the border of its cells.");
+			for (b, &other) in cell_entrances.iter().enumerate() {
+				let next = cost + inside(cell, a, b);
+				let other_id = entrance_id(&mut ids, &mut entrances, &mut costs, &mut previous, other);
+				if next < costs[other_id] {
+					costs[other_id] = next;
+					previous[other_id] = Some(id);
+					queue.push(MinSortableNode(other_id, next));
+				}
+			}
+		}
+	}
+	if best == S::INFINITY { return None; }
+	let mut way = Vec::new();
+	while let Some(id) = last {
+		way.push(entrances[id]);
+		last = previous[id];
+	}
+	way.reverse();
+	Some((best, way))
+}
+
+// Index of the entrance in the search, entrances that are seen for the first time are added with an infinite cost
+fn entrance_id<S: Scalar>(ids: &mut HashMap<Entrance, usize>, entrances: &mut Vec<Entrance>, costs: &mut Vec<S>, previous: &mut Vec<Option<usize>>, e: Entrance) -> usize {
+	*ids.entry(e).or_insert_with(|| {
+		entrances.push(e);
+		costs.push(S::INFINITY);
+		previous.push(None);
+		entrances.len() - 1
+	})
+}
+
+// Erases the nodes that the obstacles reaching beyond the local map have left outside of it.
+// The paths through them are repaired before the dead nodes are swapped out, which moves the nodes the repair starts from.
+fn trim_local_map<S: Scalar>(map: &mut JkmShortestPathMap<S>) {
+	map.erase_nodes_outside_map();
+	map.update();
+	map.swap_out_dead_nodes();
+}
+
+// Goes first horizontally, then vertically, without the first coordinate
+fn corner_path<S: Scalar>(from: (S, S), to: (S, S)) -> Vec<(S, S)> {
+	let mut points = vec![(to.0, from.1), to];
+	points.dedup();
+	points.retain(|&p| p != from);
+	points
+}
+
+// Space around the chunk, at least EPS so that it does not vanish for integers
+fn chunk_margin<S: Scalar>() -> S {
+	S::from_f64(CHUNK_MARGIN).max(S::EPS)
+}
+
+fn manhattan<S: Scalar>(a: (S, S), b: (S, S)) -> S {
+	(a.0 - b.0).abs() + (a.1 - b.1).abs()
+}
+
+fn rectangles_overlap<S: Scalar>(a: (S,S,S,S), b: (S,S,S,S)) -> bool {
+	a.0 < b.0 + b.2 && b.0 < a.0 + a.2 && a.1 < b.1 + b.3 && b.1 < a.1 + a.3
+}
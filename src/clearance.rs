@@ -0,0 +1,93 @@
+//! Width of the free corridor around each edge, for agents that do not fit through every gap
+
+use std::mem;
+
+use super::{JkmShortestPathMap, Scalar, NORTH, EAST, SOUTH, WEST};
+use hierarchy::Path;
+use touch::{Sides, horizontal, vertical};
+
+impl<S: Scalar> JkmShortestPathMap<S> {
+
+	/// Returns the shortest path from the coordinate to the destination that only uses edges with at least the given clearance,
+	/// i.e. the free space across each edge, between the obstacles on both sides of it, is at least as wide as the agent.
+	/// The path is searched with A* like find_path(), the shortest paths stored in the map are not used.
+	/// The clearance is computed for each edge that is looked at, unless track_clearance() has been called.
+	/// Returns None if there is no such path or the coordinate is outside of the map or blocked.
+	///
+	/// #Panics
+	/// Panics if the width is negative.
+	pub fn path_from_with_min_clearance(&self, x: S, y: S, width: S) -> Option<Path<S>> {
+		if width.is_nan() || width < S::ZERO { panic!("The width has to be at least 0, got {}.", width); }
+		let destination = self.get_destination_coordinates();
+		self.search((x, y), destination, Some(width))
+	}
+
+	/// Starts storing the clearance of each edge, so that path_from_with_min_clearance() does not compute it for every edge it looks at.
+	/// From then on, each change of the map also computes the clearance of all edges next to the obstacles that were inserted or removed,
+	/// which takes time proportional to the number of nodes.
+	pub fn track_clearance(&mut self) {
+		self.clearance_tracked = true;
+		for n in 0..self.graph.len() {
+			self.graph[n].clearance = [(S::NEG_INFINITY, S::ZERO); 2];
+		}
+		self.update_clearance();
+		// the clearance does not count as a change
+		self.graph.take_touched();
+	}
+
+	/// Stops storing the clearance of the edges, path_from_with_min_clearance() computes it for each edge instead
+	pub fn untrack_clearance(&mut self) {
+		self.clearance_tracked = false;
+	}
+
+	// Clearance of the edge from the node in the given direction.
+	// Falls back to computing it if it is not tracked or if the stored value belongs to an edge that has been changed since the last update.
+	pub(crate) fn edge_clearance(&self, n: usize, direction: usize) -> S {
+		let neighbour = self.graph[n].neighbours[direction].expect("There is no edge in this direction.");
+		let (n, direction, neighbour) = if direction == SOUTH || direction == WEST { (neighbour, direction - 2, n) } else { (n, direction, neighbour) };
+		let (end, width) = self.graph[n].clearance[direction];
+		let (x, y) = (self.graph[n].x, self.graph[n].y);
+		if direction == NORTH {
+			if self.clearance_tracked && end == self.graph[neighbour].y { width } else { corridor_width(&self.obstacles, self.map, self.graph[neighbour].y, y, x, vertical) }
+		}
+		else if self.clearance_tracked && end == self.graph[neighbour].x { width } else { corridor_width(&self.obstacles, self.map, x, self.graph[neighbour].x, y, horizontal) }
+	}
+
+	// Recomputes the clearance of all edges that are new or lie next to an obstacle that was inserted or removed since the last update,
+	// only if it is tracked
+	pub(crate) fn update_clearance(&mut self) {
+		let changed = mem::take(&mut self.changed_obstacles);
+		if !self.clearance_tracked { return; }
+		for n in 0..self.graph.len() {
+			let (x, y) = (self.graph[n].x, self.graph[n].y);
+			for &direction in [NORTH, EAST].iter() {
+				let neighbour = match self.graph[n].neighbours[direction] {
+					Some(neighbour) => neighbour,
+					None => continue,
+				};
+				let (from, to, at, sides): (S, S, S, Sides<S>) = if direction == NORTH { (self.graph[neighbour].y, y, x, vertical) }
+					else { (x, self.graph[neighbour].x, y, horizontal) };
+				let end = if direction == NORTH { from } else { to };
+				let moved = self.graph[n].clearance[direction].0 != end;
+				if moved || changed.iter().any(|&o| { let (o0, o1, _, _) = sides(o); o0 < to && o1 > from }) {
+					self.graph[n].clearance[direction] = (end, corridor_width(&self.obstacles, self.map, from, to, at, sides));
+				}
+			}
+		}
+	}
+}
+
+// Distance between the closest obstacles on both sides of the line from `from` to `to` at `at`,
+// the border of the map counts as an obstacle on the sides without one
+pub(crate) fn corridor_width<S: Scalar>(obstacles: &[(S,S,S,S)], map: (S,S,S,S), from: S, to: S, at: S, sides: Sides<S>) -> S {
+	let (_, _, map_start, map_end) = sides(map);
+	let (mut before, mut after) = ((at - map_start).max(S::ZERO), (map_end - at).max(S::ZERO));
+	for &o in obstacles.iter() {
+		let (o0, o1, start, end) = sides(o);
+		if o0 >= to || o1 <= from { continue; }
+		if end <= at { before = before.min(at - end); }
+		else if start >= at { after = after.min(start - at); }
+		else { return S::ZERO; }
+	}
+	before + after
+}
@@ -0,0 +1,197 @@
+//! Clusters above the graph for paths between two arbitrary coordinates. The map is split into square clusters that are
+//! connected over entrances on their borders. The costs between the entrances of a cluster are searched on the graph of the map
+//! without leaving the cluster, they are only computed again for the clusters that have been changed.
+
+use std::cmp;
+use std::collections::HashMap;
+use std::mem;
+
+use super::{JkmShortestPathMap, Scalar};
+use chunked::{self, Grid, Entrance};
+
+/// A path between two coordinates
+#[derive(Clone, PartialEq, Debug)]
+pub struct Path<S = f64> {
+	/// All checkpoints from the start to the end, including both of them. Consecutive points are aligned, unless the path jumps through a portal.
+	pub points: Vec<(S, S)>,
+	pub cost: S,
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct Hierarchy<S> {
+	grid: Grid<S>,
+	// the map the clusters have been laid out for
+	map: (S,S,S,S),
+	// costs between the entrances of each cluster on the map, the cost from entrance a to entrance b is at a * n + b,
+	// n being the number of entrances of a cluster
+	pub(crate) clusters: HashMap<(i64, i64), Vec<S>>,
+	// clusters whose costs have to be computed again
+	pub(crate) changed: Vec<(i64, i64)>,
+}
+
+impl<S: Scalar> Hierarchy<S> {
+	fn new(cluster_size: S, entrances_per_side: usize) -> Hierarchy<S> {
+		Hierarchy { grid: Grid::new(cluster_size, entrances_per_side), map: (S::ZERO, S::ZERO, S::ZERO, S::ZERO), clusters: HashMap::new(), changed: Vec::new() }
+	}
+
+	// Marks all clusters that the rectangle overlaps or touches, their costs are computed again by update_hierarchy()
+	pub(crate) fn mark(&mut self, (x, y, w, h): (S,S,S,S)) {
+		let (mut min, max) = (self.grid.cell_at(x, y), self.grid.cell_at(x + w, y + h));
+		let (cx, cy, _, _) = self.grid.area(min);
+		if x == cx { min.0 -= 1; }
+		if y == cy { min.1 -= 1; }
+		for cx in min.0..max.0 + 1 {
+			for cy in min.1..max.1 + 1 {
+				if self.clusters.contains_key(&(cx, cy)) { self.changed.push((cx, cy)); }
+			}
+		}
+	}
+
+	// Lays the clusters out for a map of another size, the clusters along the old and the new border are computed again
+	fn cover(&mut self, map: (S,S,S,S)) {
+		let old = self.map;
+		let (mx, my, mw, mh) = map;
+		let (first, last) = (self.grid.cell_at(mx, my), self.grid.cell_at(mx + mw, my + mh));
+		let mut clusters = HashMap::new();
+		for cx in first.0..last.0 + 1 {
+			for cy in first.1..last.1 + 1 {
+				let (x, y, w, h) = self.grid.area((cx, cy));
+				// the cells beyond the map that only touch it on their border are left out
+				if x >= mx + mw && cx > first.0 || y >= my + mh && cy > first.1 { continue; }
+				if x > old.0 && y > old.1 && x + w < old.0 + old.2 && y + h < old.1 + old.3 {
+					if let Some(costs) = self.clusters.remove(&(cx, cy)) {
+						clusters.insert((cx, cy), costs);
+						continue;
+					}
+				}
+				clusters.insert((cx, cy), Vec::new());
+				self.changed.push((cx, cy));
+			}
+		}
+		self.clusters = clusters;
+		self.map = map;
+	}
+
+	// Part of the cluster that lies on the map
+	fn area(&self, cluster: (i64, i64)) -> (S,S,S,S) {
+		let (x, y, w, h) = self.grid.area(cluster);
+		let (mx, my, mw, mh) = self.map;
+		let (left, top) = (x.max(mx), y.max(my));
+		let (right, bottom) = ((x + w).min(mx + mw), (y + h).min(my + mh));
+		(left, top, right - left, bottom - top)
+	}
+
+	// The cluster that contains the coordinate, coordinates on the east or south border of the map belong to the cluster before it
+	fn cluster_at(&self, (x, y): (S, S)) -> Option<(i64, i64)> {
+		let (cx, cy) = self.grid.cell_at(x, y);
+		[(cx, cy), (cx - 1, cy), (cx, cy - 1), (cx - 1, cy - 1)].iter().cloned()
+			.find(|&c| self.clusters.contains_key(&c) && within(self.area(c), (x, y)))
+	}
+
+	// Plans the path over the entrances and refines each part of it inside of its cluster
+	fn path(&self, spm: &JkmShortestPathMap<S>, from: (S, S), to: (S, S)) -> Option<Path<S>> {
+		let (start, end) = (self.cluster_at(from)?, self.cluster_at(to)?);
+		let cost = |path: Option<Path<S>>| path.map_or(S::INFINITY, |path| path.cost);
+		let direct = if start == end { cost(spm.search_within(from, &[to], Some(self.area(start)), None).pop().and_then(|path| path)) } else { S::INFINITY };
+		let starts: Vec<(S, S)> = self.grid.entrances(start).into_iter().map(|e| self.grid.position(e)).collect();
+		let from_start: Vec<S> = spm.search_within(from, &starts, Some(self.area(start)), None).into_iter().map(cost).collect();
+		let to_end: Vec<S> = self.grid.entrances(end).into_iter()
+			.map(|e| cost(spm.search_within(self.grid.position(e), &[to], Some(self.area(end)), None).pop().and_then(|path| path)))
+			.collect();
+
+		let (mut min, mut max) = (start, start);
+		for &(x, y) in self.clusters.keys() {
+			min = (cmp::min(min.0, x), cmp::min(min.1, y));
+			max = (cmp::max(max.0, x), cmp::max(max.1, y));
+		}
+		let n = 4 * self.grid.entrances_per_side;
+		let inside = |cluster: (i64, i64), a: usize, b: usize| self.clusters.get(&cluster).map_or(S::INFINITY, |costs| costs[a * n + b]);
+		let (_, entrances) = chunked::plan(&self.grid, (start, end), (min, max), direct, &from_start, &to_end, inside)?;
+
+		// parts of the path with the cluster they lie in
+		let mut legs = Vec::new();
+		let positions: Vec<(S, S)> = entrances.iter().map(|&e| self.grid.position(e)).collect();
+		if let (Some(&first), Some(&last)) = (positions.first(), positions.last()) {
+			legs.push((start, from, first));
+			for (i, pair) in entrances.windows(2).enumerate() {
+				legs.push((self.grid.common_cell(pair[0], pair[1]), positions[i], positions[i + 1]));
+			}
+			legs.push((end, last, to));
+		}
+		else {
+			legs.push((start, from, to));
+		}
+		let mut path = Path { points: vec![from], cost: S::ZERO };
+		for (cluster, a, b) in legs {
+			let leg = spm.search_within(a, &[b], Some(self.area(cluster)), None).pop()??;
+			path.points.extend(leg.points.into_iter().skip(1));
+			path.cost += leg.cost;
+		}
+		path.points.dedup();
+		Some(path)
+	}
+}
+
+impl<S: Scalar> JkmShortestPathMap<S> {
+
+	/// Covers the map with square clusters of the given size that are connected over entrances on their borders,
+	/// like the chunks of a ChunkedWorld. They are used by hierarchical_path(). Whenever an obstacle, a directional zone or a portal
+	/// changes, the costs between the entrances are computed again for the clusters it lies in.
+	/// Calling it again replaces the clusters.
+	///
+	/// #Panics
+	/// Panics if the cluster size is not positive or if there are no entrances.
+	pub fn enable_hierarchy(&mut self, cluster_size: S, entrances_per_side: usize) {
+		if cluster_size.is_nan() || cluster_size <= S::ZERO { panic!("The cluster size has to be positive, got {}.", cluster_size); }
+		if entrances_per_side == 0 { panic!("Clusters need at least one entrance per side."); }
+		self.hierarchy = Some(Box::new(Hierarchy::new(cluster_size, entrances_per_side)));
+		self.update_hierarchy();
+	}
+
+	/// Drops the clusters created by enable_hierarchy()
+	pub fn disable_hierarchy(&mut self) {
+		self.hierarchy = None;
+	}
+
+	/// Returns a path between two arbitrary coordinates on the map, independent of the destination.
+	/// It is planned over the entrances of the clusters first and then refined inside of each cluster on the graph of the map.
+	/// Paths change clusters only at entrances, therefore the path is not always the shortest one.
+	/// Without enable_hierarchy(), this is the same as find_path().
+	/// Returns None if there is no path.
+	pub fn hierarchical_path(&self, from: (S, S), to: (S, S)) -> Option<Path<S>> {
+		match self.hierarchy {
+			Some(ref hierarchy) => hierarchy.path(self, from, to),
+			None => self.find_path(from, to),
+		}
+	}
+
+	// Computes the costs between the entrances of all clusters that have been marked since the last update
+	pub(crate) fn update_hierarchy(&mut self) {
+		let mut hierarchy = match self.hierarchy.take() {
+			Some(hierarchy) => hierarchy,
+			None => return,
+		};
+		if hierarchy.map != self.map { hierarchy.cover(self.map); }
+		let mut changed = mem::take(&mut hierarchy.changed);
+		changed.sort_unstable();
+		changed.dedup();
+		for cluster in changed {
+			let entrances: Vec<Entrance> = hierarchy.grid.entrances(cluster);
+			let positions: Vec<(S, S)> = entrances.into_iter().map(|e| hierarchy.grid.position(e)).collect();
+			let costs = self.costs_within(&positions, hierarchy.area(cluster));
+			hierarchy.clusters.insert(cluster, costs);
+		}
+		self.hierarchy = Some(hierarchy);
+	}
+
+	// Marks the clusters that the rectangle overlaps, see Hierarchy::mark()
+	pub(crate) fn mark_clusters(&mut self, rectangle: (S,S,S,S)) {
+		if let Some(ref mut hierarchy) = self.hierarchy {
+			hierarchy.mark(rectangle);
+		}
+	}
+}
+
+fn within<S: Scalar>((x, y, w, h): (S,S,S,S), (px, py): (S, S)) -> bool {
+	px >= x && px <= x + w && py >= y && py <= y + h
+}
@@ -0,0 +1,346 @@
+//! Change log of the shortest path map, used to restore earlier states with checkpoint() and rollback()
+//! Nodes are only copied the first time they are modified after a checkpoint, therefore taking a checkpoint is cheap.
+
+use std::collections::BinaryHeap;
+use std::ops::{Deref, Index, IndexMut};
+
+use super::{JkmShortestPathMap, Scalar, GraphNode};
+use schedule::Timetable;
+use zones::DirectionalZone;
+use portals::Portal;
+use layers::Layer;
+use border::BorderMode;
+use touch::TouchPolicy;
+
+/// Identifies a state of a JkmShortestPathMap that was stored with checkpoint().
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct StateToken(usize);
+
+/// Storage for the nodes of the graph.
+/// Reading works like on a slice, all write access goes through index_mut(), push() and pop() so that
+/// the previous state of a node can be recorded while there is an open checkpoint.
+#[derive(Clone, Debug)]
+pub(crate) struct NodeStore<S> {
+	nodes: Vec<Box<GraphNode<S>>>,
+	// epoch in which each node has been recorded the last time
+	stamps: Vec<usize>,
+	// epoch of the newest checkpoint, 0 if nothing is recorded
+	epoch: usize,
+	log: Vec<NodeChange<S>>,
+	// nodes that have been written, pushed or popped since the last take_touched(), used to update what depends on them
+	touched: Vec<usize>,
+	is_touched: Vec<bool>,
+}
+
+#[derive(Clone, Debug)]
+enum NodeChange<S> {
+	Modified(usize, Box<GraphNode<S>>, usize),
+	Pushed,
+	Popped(Box<GraphNode<S>>, usize),
+}
+
+impl<S: Scalar> NodeStore<S> {
+	pub(crate) fn new() -> NodeStore<S> {
+		NodeStore {
+			nodes: Vec::new(),
+			stamps: Vec::new(),
+			epoch: 0,
+			log: Vec::new(),
+			touched: Vec::new(),
+			is_touched: Vec::new(),
+		}
+	}
+
+	pub(crate) fn push(&mut self, node: Box<GraphNode<S>>) {
+		if self.epoch != 0 {
+			self.log.push(NodeChange::Pushed);
+		}
+		self.nodes.push(node);
+		self.stamps.push(self.epoch);
+		self.is_touched.push(false);
+		self.touch(self.nodes.len() - 1);
+	}
+
+	pub(crate) fn pop(&mut self) -> Option<Box<GraphNode<S>>> {
+		if !self.nodes.is_empty() {
+			self.touch(self.nodes.len() - 1);
+			self.is_touched.pop();
+		}
+		let node = self.nodes.pop();
+		if let Some(stamp) = self.stamps.pop() {
+			if self.epoch != 0 {
+				if let Some(ref n) = node {
+					self.log.push(NodeChange::Popped(n.clone(), stamp));
+				}
+			}
+		}
+		node
+	}
+
+	fn touch(&mut self, i: usize) {
+		if !self.is_touched[i] {
+			self.is_touched[i] = true;
+			self.touched.push(i);
+		}
+	}
+
+	// Nodes that have been written, pushed or popped since the last take_touched(), in no particular order
+	pub(crate) fn touched(&self) -> &[usize] {
+		&self.touched
+	}
+
+	// Returns all nodes that have been written, pushed or popped since the last call, the popped ones are beyond the end
+	pub(crate) fn take_touched(&mut self) -> Vec<usize> {
+		let mut touched = std::mem::take(&mut self.touched);
+		touched.sort_unstable();
+		touched.dedup();
+		for &i in touched.iter() {
+			if i < self.is_touched.len() { self.is_touched[i] = false; }
+		}
+		touched
+	}
+
+	// Starts a new epoch, all nodes will be recorded again when they are modified the next time
+	fn start_epoch(&mut self, epoch: usize) -> usize {
+		self.epoch = epoch;
+		self.log.len()
+	}
+
+	// Undoes all changes after the given position in the log
+	fn undo_until(&mut self, log_position: usize, epoch: usize) {
+		while self.log.len() > log_position {
+			match self.log.pop() {
+				Some(NodeChange::Modified(i, node, stamp)) => {
+					self.nodes[i] = node;
+					self.stamps[i] = stamp;
+					self.touch(i);
+				}
+				Some(NodeChange::Pushed) => {
+					self.touch(self.nodes.len() - 1);
+					self.nodes.pop();
+					self.stamps.pop();
+					self.is_touched.pop();
+				}
+				Some(NodeChange::Popped(node, stamp)) => {
+					self.nodes.push(node);
+					self.stamps.push(stamp);
+					self.is_touched.push(false);
+					self.touch(self.nodes.len() - 1);
+				}
+				None => {}
+			}
+		}
+		self.epoch = epoch;
+	}
+
+	fn stop_recording(&mut self) {
+		self.epoch = 0;
+		self.log = Vec::new();
+	}
+}
+
+impl<S> Deref for NodeStore<S> {
+	type Target = [Box<GraphNode<S>>];
+	fn deref(&self) -> &[Box<GraphNode<S>>] {
+		&self.nodes
+	}
+}
+
+impl<S> Index<usize> for NodeStore<S> {
+	type Output = Box<GraphNode<S>>;
+	fn index(&self, i: usize) -> &Box<GraphNode<S>> {
+		&self.nodes[i]
+	}
+}
+
+impl<S: Scalar> IndexMut<usize> for NodeStore<S> {
+	fn index_mut(&mut self, i: usize) -> &mut Box<GraphNode<S>> {
+		self.touch(i);
+		if self.stamps[i] < self.epoch {
+			self.log.push(NodeChange::Modified(i, self.nodes[i].clone(), self.stamps[i]));
+			self.stamps[i] = self.epoch;
+		}
+		&mut self.nodes[i]
+	}
+}
+
+/// Everything besides the nodes that is needed to restore a checkpoint
+#[derive(Clone, Debug)]
+pub(crate) struct History<S> {
+	checkpoints: Vec<Checkpoint<S>>,
+	obstacle_log: Vec<ObstacleChange<S>>,
+	next_id: usize,
+}
+
+#[derive(Clone, Debug)]
+struct Checkpoint<S> {
+	id: usize,
+	node_log: usize,
+	obstacle_log: usize,
+	dead_nodes: BinaryHeap<usize>,
+	start_point_index: usize,
+	end_point_index: usize,
+	timetable: Timetable<S>,
+	zones: Vec<DirectionalZone<S>>,
+	portals: Vec<Portal<S>>,
+	map: (S,S,S,S),
+	layers: Vec<Layer<S>>,
+	border: [BorderMode<S>; 4],
+	touch: TouchPolicy,
+}
+
+#[derive(Clone, Debug)]
+enum ObstacleChange<S> {
+	Pushed,
+	SwapRemoved(usize, (S,S,S,S)),
+}
+
+impl<S> History<S> {
+	pub(crate) fn new() -> History<S> {
+		History {
+			checkpoints: Vec::new(),
+			obstacle_log: Vec::new(),
+			next_id: 1,
+		}
+	}
+}
+
+impl<S: Scalar> JkmShortestPathMap<S> {
+
+	/// Stores the current state of the map, it can be restored later on by calling rollback() with the returned token.
+	/// Checkpoints can be nested, rolling back to an earlier checkpoint discards all checkpoints taken after it.
+	/// While a checkpoint exists, all changes to the map are recorded. Call commit() when the state is no longer needed.
+	pub fn checkpoint(&mut self) -> StateToken {
+		let id = self.history.next_id;
+		self.history.next_id += 1;
+		let node_log = self.graph.start_epoch(id);
+		let checkpoint = Checkpoint {
+			id,
+			node_log,
+			obstacle_log: self.history.obstacle_log.len(),
+			dead_nodes: self.dead_nodes.clone(),
+			start_point_index: self.start_point_index,
+			end_point_index: self.end_point_index,
+			timetable: self.timetable.clone(),
+			zones: self.zones.clone(),
+			portals: self.portals.clone(),
+			map: self.map,
+			layers: self.layers.clone(),
+			border: self.border,
+			touch: self.touch,
+		};
+		self.history.checkpoints.push(checkpoint);
+		StateToken(id)
+	}
+
+	/// Restores the graph, the obstacles, the directional zones, the portals, the size of the map, the shortest paths and the time to the state they had when the checkpoint was taken.
+	/// The token stays valid, therefore it is possible to roll back several times to the same state. Path tokens are no longer valid afterwards.
+	/// Returns false and does nothing if the checkpoint has been committed or discarded by an earlier rollback.
+	pub fn rollback(&mut self, token: StateToken) -> bool {
+		let position = match self.history.checkpoints.iter().position(|c| c.id == token.0) {
+			Some(p) => p,
+			None => return false,
+		};
+		self.history.checkpoints.truncate(position + 1);
+		let checkpoint = &self.history.checkpoints[position];
+		self.graph.undo_until(checkpoint.node_log, checkpoint.id);
+		// obstacles, zones and portals that are taken away or put back change the clusters they lie in
+		let mut undone = Vec::new();
+		while self.history.obstacle_log.len() > checkpoint.obstacle_log {
+			match self.history.obstacle_log.pop() {
+				Some(ObstacleChange::Pushed) => { undone.extend(self.obstacles.pop()); }
+				Some(ObstacleChange::SwapRemoved(i, obstacle)) => {
+					undone.push(obstacle);
+					self.obstacles.push(obstacle);
+					let last = self.obstacles.len() - 1;
+					self.obstacles.swap(i, last);
+				}
+				None => {}
+			}
+		}
+		let obstacles_undone = undone.len();
+		// zones and portals are only ever added, the ones that are dropped are those added after the checkpoint
+		undone.extend(self.zones[checkpoint.zones.len()..].iter().map(|zone| zone.area()));
+		for portal in self.portals[checkpoint.portals.len()..].iter() {
+			undone.push((portal.from.0, portal.from.1, S::ZERO, S::ZERO));
+			undone.push((portal.to.0, portal.to.1, S::ZERO, S::ZERO));
+		}
+		self.dead_nodes = checkpoint.dead_nodes.clone();
+		self.start_point_index = checkpoint.start_point_index;
+		self.end_point_index = checkpoint.end_point_index;
+		self.timetable = checkpoint.timetable.clone();
+		self.zones = checkpoint.zones.clone();
+		self.portals = checkpoint.portals.clone();
+		self.map = checkpoint.map;
+		self.layers = checkpoint.layers.clone();
+		self.border = checkpoint.border;
+		self.touch = checkpoint.touch;
+		self.change_log.forget();
+		for &obstacle in undone[..obstacles_undone].iter() {
+			self.reset_seams(obstacle);
+		}
+		let touched = self.graph.take_touched();
+		self.update_edge_index(&touched);
+		let live = self.graph.len();
+		for &n in touched.iter().filter(|&&n| n < live) {
+			self.note_path_change(n);
+		}
+		for area in undone {
+			self.mark_clusters(area);
+		}
+		self.update_hierarchy();
+		true
+	}
+
+	/// Keeps all changes made since the checkpoint was taken. The checkpoint and all checkpoints taken after it
+	/// can no longer be restored. Once no checkpoint is left, the map stops recording changes.
+	/// Returns false if there was no such checkpoint.
+	pub fn commit(&mut self, token: StateToken) -> bool {
+		let position = match self.history.checkpoints.iter().position(|c| c.id == token.0) {
+			Some(p) => p,
+			None => return false,
+		};
+		self.history.checkpoints.truncate(position);
+		if let Some(newest) = self.history.checkpoints.last() {
+			self.graph.epoch = newest.id;
+		}
+		else {
+			self.graph.stop_recording();
+			self.history.obstacle_log = Vec::new();
+		}
+		true
+	}
+
+	// All modifications of the obstacle list have to go through these two functions
+	pub(crate) fn push_obstacle(&mut self, obstacle: (S,S,S,S)) {
+		if !self.history.checkpoints.is_empty() {
+			self.history.obstacle_log.push(ObstacleChange::Pushed);
+		}
+		self.obstacles.push(obstacle);
+		self.seams.add(obstacle, &self.obstacles);
+		self.changed_obstacles.push(obstacle);
+		self.change_log.record(obstacle);
+		self.mark_clusters(obstacle);
+	}
+
+	pub(crate) fn swap_remove_obstacle(&mut self, i: usize) -> (S,S,S,S) {
+		let obstacle = self.obstacles.swap_remove(i);
+		self.reset_seams(obstacle);
+		if !self.history.checkpoints.is_empty() {
+			self.history.obstacle_log.push(ObstacleChange::SwapRemoved(i, obstacle));
+		}
+		self.changed_obstacles.push(obstacle);
+		self.change_log.record(obstacle);
+		self.mark_clusters(obstacle);
+		obstacle
+	}
+
+	// Finds the seams of the obstacle again after it has been taken away or put back
+	fn reset_seams(&mut self, obstacle: (S,S,S,S)) {
+		self.seams.remove(obstacle);
+		// the same obstacle can be inserted more than once
+		if self.obstacles.contains(&obstacle) {
+			self.seams.add(obstacle, &self.obstacles);
+		}
+	}
+}
@@ -0,0 +1,95 @@
+//! The .jkmmap format of the map inspector: one line per node as x|y|north|east|south|west|shortest path|cost,
+//! a line with #, then one line per obstacle as x|y|w|h. Missing neighbours and paths are written as -.
+
+use std::io::{self, BufRead, Write};
+
+use super::{JkmShortestPathMap, Scalar};
+use border::BorderMode;
+
+impl<S: Scalar> JkmShortestPathMap<S> {
+
+	/// Writes the graph and the obstacles in the .jkmmap format, which can be viewed with the map inspector.
+	/// Erased nodes are written as well, so that the indices of the neighbours stay the same.
+	pub fn write_jkmmap<W: Write>(&self, out: &mut W) -> io::Result<()> {
+		let index = |n: Option<usize>| n.map_or("-".to_string(), |n| n.to_string());
+		for node in self.graph.iter() {
+			writeln!(out, "{}|{}|{}|{}|{}|{}|{}|{}", node.x, node.y,
+				index(node.neighbours[0]), index(node.neighbours[1]), index(node.neighbours[2]), index(node.neighbours[3]),
+				index(node.shortest_path), node.cost)?;
+		}
+		writeln!(out, "#")?;
+		for &(x, y, w, h) in self.obstacles.iter() {
+			writeln!(out, "{}|{}|{}|{}", x, y, w, h)?;
+		}
+		Ok(())
+	}
+
+	/// Builds a map again from a .jkmmap file. The map is the rectangle around all nodes, the destination is the node with cost 0.
+	/// The start point is not stored in the file, it has to be given. Obstacles that lie completely outside of a side of the map
+	/// close this side, see set_border(), all others are inserted in the order of the file.
+	/// Returns a description of the problem if the file can not be read.
+	pub fn read_jkmmap<R: BufRead>(input: R, start: (S, S)) -> Result<JkmShortestPathMap<S>, String> {
+		let mut nodes = Vec::new();
+		let mut obstacles = Vec::new();
+		let mut in_obstacles = false;
+		for (number, line) in input.lines().enumerate() {
+			let line = line.map_err(|e| e.to_string())?;
+			let line = line.trim();
+			if line.is_empty() { continue; }
+			if line == "#" { in_obstacles = true; continue; }
+			let fields: Vec<&str> = line.split('|').collect();
+			let number_at = |i: usize| -> Result<S, String> {
+				fields.get(i).and_then(|f| f.parse::<f64>().ok()).map(S::from_f64)
+					.ok_or_else(|| format!("Line {} is not valid: {}", number + 1, line))
+			};
+			if in_obstacles {
+				obstacles.push((number_at(0)?, number_at(1)?, number_at(2)?, number_at(3)?));
+			}
+			else if fields.len() == 8 {
+				let (x, y) = (number_at(0)?, number_at(1)?);
+				if x != S::NEG_INFINITY {
+					let cost = fields[7].parse::<f64>().ok().map(S::from_f64);
+					nodes.push((x, y, cost));
+				}
+			}
+			else {
+				return Err(format!("Line {} is not a node: {}", number + 1, line));
+			}
+		}
+		let first = match nodes.first() {
+			Some(&(x, y, _)) => (x, y),
+			None => return Err("There are no nodes in the file.".to_string()),
+		};
+		let (mut left, mut top, mut right, mut bottom) = (first.0, first.1, first.0, first.1);
+		for &(x, y, _) in nodes.iter() {
+			left = left.min(x);
+			top = top.min(y);
+			right = right.max(x);
+			bottom = bottom.max(y);
+		}
+		let end = match nodes.iter().find(|&&(_, _, cost)| cost == Some(S::ZERO)) {
+			Some(&(x, y, _)) => (x, y),
+			None => return Err("There is no node with cost 0 that could be the destination.".to_string()),
+		};
+		let map = (left, top, right - left, bottom - top);
+		if start == end || start.0 < left || start.0 > right || start.1 < top || start.1 > bottom {
+			return Err(format!("The start point ({}, {}) has to be on the map and different from the destination.", start.0, start.1));
+		}
+
+		let mut spm = JkmShortestPathMap::new(start, end, map);
+		let mut border = [BorderMode::Open; 4];
+		for &(x, y, w, h) in obstacles.iter() {
+			let side = if y + h <= top { Some((0, h)) }
+				else if x >= right { Some((1, w)) }
+				else if y >= bottom { Some((2, h)) }
+				else if x + w <= left { Some((3, w)) }
+				else { None };
+			match side {
+				Some((side, thickness)) => border[side] = BorderMode::Closed(thickness),
+				None => spm.insert_obstacle(x, y, w, h),
+			}
+		}
+		spm.set_border(border[0], border[1], border[2], border[3]);
+		Ok(spm)
+	}
+}
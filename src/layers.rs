@@ -0,0 +1,144 @@
+//! Several floors in one map. All layers are placed next to each other on one big map and the space between them is blocked,
+//! connections between layers are portals.
+
+use super::{JkmShortestPathMap, Scalar};
+
+/// Width of the blocked space between two layers
+const LAYER_GAP: f64 = 1.0;
+
+/// A rectangle (x, y, w, h) in its own coordinates, it is moved by offset along the x-axis on the map
+#[derive(Copy, Clone, Debug)]
+pub(crate) struct Layer<S> {
+	area: (S,S,S,S),
+	offset: S,
+}
+
+impl<S: Scalar> Layer<S> {
+	pub(crate) fn new(area: (S,S,S,S)) -> Layer<S> {
+		Layer { area, offset: S::ZERO }
+	}
+}
+
+impl<S: Scalar> JkmShortestPathMap<S> {
+
+	/// Creates a map with several layers, each given as a rectangle (x, y, w, h) in its own coordinates.
+	/// Start and destination are given as (layer, x, y). Without connections, there is no path between layers,
+	/// see add_layer_connection().
+	///
+	/// #Panics
+	/// Panics if there are no layers, a layer index is out of range or for all reasons new() panics.
+	pub fn new_layered(start: (usize, S, S), end: (usize, S, S), layers: &[(S,S,S,S)]) -> JkmShortestPathMap<S> {
+		let gap = layer_gap::<S>();
+		if layers.is_empty() { panic!("A map needs at least one layer."); }
+		let mut placed = Vec::new();
+		let mut cursor = S::ZERO;
+		for &(x, y, w, h) in layers.iter() {
+			placed.push(Layer { area: (x, y, w, h), offset: cursor - x });
+			cursor += w + gap;
+		}
+		let top = layers.iter().map(|l| l.1).fold(S::INFINITY, S::min);
+		let bottom = layers.iter().map(|l| l.1 + l.3).fold(S::NEG_INFINITY, S::max);
+		let map = (S::ZERO, top, cursor - gap, bottom - top);
+
+		let to_map = |(layer, x, y): (usize, S, S)| -> (S, S) {
+			match placed.get(layer) {
+				Some(l) => (x + l.offset, y),
+				None => panic!("There is no layer {}.", layer),
+			}
+		};
+		let mut spm = JkmShortestPathMap::new(to_map(start), to_map(end), map);
+		spm.layers = placed;
+
+		// block everything that does not belong to a layer, the blockers overlap each other so that no path goes between them
+		let d = gap / S::from_f64(2.0);
+		for i in 0..spm.layers.len() {
+			let (x, y, w, h) = spm.layers[i].area;
+			let x = x + spm.layers[i].offset;
+			if i + 1 < spm.layers.len() {
+				spm.layer_blockers.push((x + w, top - d, gap, bottom - top + d + d));
+			}
+			if y > top {
+				spm.layer_blockers.push((x - d, top - d, w + d + d, y - top + d));
+			}
+			if y + h < bottom {
+				spm.layer_blockers.push((x - d, y + h, w + d + d, bottom - y - h + d));
+			}
+		}
+		for (x, y, w, h) in spm.layer_blockers.clone() {
+			spm.insert_obstacle(x, y, w, h);
+		}
+		// the blockers stick out of the map and the edges around them would connect the layers
+		spm.erase_nodes_outside_map();
+		spm.recompute_paths();
+		spm.after_update();
+		spm
+	}
+
+	/// Number of layers, a map created with new() has exactly one
+	pub fn layer_count(&self) -> usize {
+		self.layers.len()
+	}
+
+	/// Converts a coordinate of a layer into a coordinate of the whole map, as it is used by all functions that do not take a layer
+	///
+	/// #Panics
+	/// Panics if there is no such layer
+	pub fn map_position(&self, layer: usize, x: S, y: S) -> (S, S) {
+		(x + self.layers[layer].offset, y)
+	}
+
+	/// Converts a coordinate of the whole map to (layer, x, y), returns None if the coordinate is not on any layer
+	pub fn layer_position(&self, x: S, y: S) -> Option<(usize, S, S)> {
+		self.layers.iter().position(|l| {
+			let (lx, ly, lw, lh) = l.area;
+			x - l.offset >= lx && x - l.offset <= lx + lw && y >= ly && y <= ly + lh
+		}).map(|i| (i, x - self.layers[i].offset, y))
+	}
+
+	/// Connects two points on two layers, like stairs. Paths can go in both directions at the given cost.
+	/// See add_portal() for details.
+	pub fn add_layer_connection(&mut self, layer_a: usize, a: (S, S), layer_b: usize, b: (S, S), cost: S) {
+		let from = self.map_position(layer_a, a.0, a.1);
+		let to = self.map_position(layer_b, b.0, b.1);
+		self.add_portal(from, to, cost, true);
+	}
+
+	/// Inserts an obstacle on a layer. Parts of the obstacle that reach further than the space between layers are cut off.
+	pub fn insert_obstacle_on(&mut self, layer: usize, x: S, y: S, w: S, h: S) {
+		let (x, y, w, h) = self.obstacle_on(layer, (x, y, w, h));
+		self.insert_obstacle(x, y, w, h);
+	}
+
+	/// Removes an obstacle that was inserted with insert_obstacle_on() with the same parameters
+	pub fn remove_obstacle_on(&mut self, layer: usize, x: S, y: S, w: S, h: S) {
+		let (x, y, w, h) = self.obstacle_on(layer, (x, y, w, h));
+		self.remove_obstacle(x, y, w, h);
+	}
+
+	/// Same as nearest_checkpoint() with coordinates of a layer, the result is given as (layer, x, y)
+	pub fn nearest_checkpoint_on(&self, layer: usize, x: S, y: S) -> Option<(usize, S, S)> {
+		let (x, y) = self.map_position(layer, x, y);
+		self.nearest_checkpoint(x, y).and_then(|(x, y)| self.layer_position(x, y))
+	}
+
+	/// Same as next_checkpoint() with coordinates of a layer, the result is given as (layer, x, y).
+	/// If the path goes through a connection to another layer, the checkpoint is on that layer.
+	pub fn next_checkpoint_on(&self, layer: usize, x: S, y: S) -> Option<(usize, S, S)> {
+		let (x, y) = self.map_position(layer, x, y);
+		self.next_checkpoint(x, y).and_then(|(x, y)| self.layer_position(x, y))
+	}
+
+	// Moves the obstacle onto the map and cuts it to the layer and half of the space around it
+	fn obstacle_on(&self, layer: usize, (x, y, w, h): (S,S,S,S)) -> (S,S,S,S) {
+		let (lx, _, lw, _) = self.layers[layer].area;
+		let d = layer_gap::<S>() / S::from_f64(2.0);
+		let left = x.max(lx - d);
+		let right = (x + w).min(lx + lw + d);
+		(left + self.layers[layer].offset, y, right - left, h)
+	}
+}
+
+// At least two EPS, so that half of the gap is not zero for integers
+fn layer_gap<S: Scalar>() -> S {
+	S::from_f64(LAYER_GAP).max(S::EPS + S::EPS)
+}
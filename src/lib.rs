@@ -3,28 +3,126 @@
 
 #[cfg(test)]
 pub mod unit_tests;
+mod history;
+mod query;
+mod snapshot;
+mod shared;
+mod regions;
+mod schedule;
+mod toggle;
+mod zones;
+mod portals;
+mod layers;
+mod chunked;
+mod hierarchy;
+mod astar;
+mod overlay;
+mod lookup;
+mod resize;
+mod border;
+mod scalar;
+mod tiles;
+mod touch;
+mod clearance;
+mod changes;
+mod validity;
+mod stats;
+mod validate;
+mod jkmmap;
 
 use std::cmp::Ordering;
 use std::collections::BinaryHeap;
 
-const EPS: f64 = 1.0/1048576.0;
+use history::{NodeStore, History};
+use query::PathGraph;
+use schedule::Timetable;
+use zones::DirectionalZone;
+use portals::{Portal, PortalEnd, PORTAL};
+use layers::Layer;
+use hierarchy::Hierarchy;
+use validity::ChangeLog;
+use stats::Counters;
+use overlay::Overlay;
+use lookup::EdgeIndex;
+use touch::Seams;
+pub use history::StateToken;
+pub use snapshot::PathSnapshot;
+pub use shared::SharedPathMap;
+pub use regions::DESTINATION_REGION;
+pub use schedule::{Schedule, ObstacleId};
+pub use zones::Direction;
+pub use portals::Step;
+pub use chunked::ChunkedWorld;
+pub use hierarchy::Path;
+pub use border::{BorderMode, DEFAULT_BORDER_THICKNESS};
+pub use scalar::Scalar;
+pub use tiles::tile_rectangles;
+pub use touch::TouchPolicy;
+pub use changes::PathChanges;
+pub use validity::PathToken;
+pub use stats::{Stats, UpdateStats, InsertPhases};
 
 /// Stores a graph with the shortest path from each node to the destination.
 /// To recompute this, it also keeps in memory what obstacles there are, therefore, if the actual map changes this struct has to be notified.
-/// The map is initially only the border for where obstacles can be placed, to make it blocking call add_map_border() on the shortest path map.
-pub struct JkmShortestPathMap {
-	graph: Vec<Box<GraphNode>>,
-	obstacles: Vec<(f64,f64,f64,f64)>,
+/// The map is initially only the border for where obstacles can be placed, to make it blocking call add_map_border() or set_border() on the shortest path map.
+/// All queries only need shared access, the map is Send and Sync. To query it from several threads while it is updated, see SharedPathMap.
+/// Coordinates and costs are f64 unless another Scalar is chosen, e.g. integers for results that are identical on every machine.
+#[derive(Clone, Debug)]
+pub struct JkmShortestPathMap<S = f64> {
+	graph: NodeStore<S>,
+	obstacles: Vec<(S,S,S,S)>,
 	start_point_index: usize,
 	end_point_index: usize,
-	map: (f64,f64,f64,f64),
+	map: (S,S,S,S),
 	dead_nodes: BinaryHeap<usize>,
 	update_root: Vec<usize>,
+	history: History<S>,
+	timetable: Timetable<S>,
+	zones: Vec<DirectionalZone<S>>,
+	portals: Vec<Portal<S>>,
+	layers: Vec<Layer<S>>,
+	// obstacles between the layers of new_layered(), they can not be removed
+	layer_blockers: Vec<(S,S,S,S)>,
+	hierarchy: Option<Box<Hierarchy<S>>>,
+	// north, east, south, west
+	border: [BorderMode<S>; 4],
+	touch: TouchPolicy,
+	// borders and corners shared by touching obstacles, kept with both policies
+	seams: Seams<S>,
+	// whether the clearance of the edges is stored, see track_clearance()
+	clearance_tracked: bool,
+	// inserted and removed since the last update, the clearance of the edges next to them has to be computed again
+	changed_obstacles: Vec<(S,S,S,S)>,
+	// positions of the nodes whose path changed since the path changes were taken the last time, None if they are not tracked
+	tracked_paths: Option<Vec<(S, S)>>,
+	change_log: ChangeLog<S>,
+	counters: Counters,
+	edges: EdgeIndex<S>,
+	// larger than all regions that are in use
+	next_region: usize,
+}
+
+/// The effect an obstacle would have on the path from the start point to the destination, see JkmShortestPathMap::would_block()
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct PlacementImpact<S = f64> {
+	/// Whether there would still be a path from the start point to the destination
+	pub start_reachable: bool,
+	/// Cost of the shortest path from the start point without the obstacle, infinity if there is no path
+	pub cost_before: S,
+	/// Cost of the shortest path from the start point with the obstacle, infinity if there is no path
+	pub cost_after: S,
+}
+
+impl<S: Scalar> PlacementImpact<S> {
+	/// How much longer the shortest path from the start point would become
+	pub fn cost_increase(&self) -> S {
+		self.cost_after - self.cost_before
+	}
 }
 
 	// line: (x, y, x2)
 	// obstacle: (x, y, w, h)
-	fn h_line_touches_obstacle (line:(f64, f64, f64), obstacle: (f64, f64, f64, f64) ) -> bool {
+	fn h_line_touches_obstacle<S: Scalar> (line:(S, S, S), obstacle: (S, S, S, S) ) -> bool {
 		debug_assert!(line.0 <= line.2, "Parameters must be in order. Got {} and {} in this order.", line.0, line.1);
 		line.0 < obstacle.0 + obstacle.2 && line.2 > obstacle.0  
 		&& line.1 >= obstacle.1 && line.1 <= obstacle.1 + obstacle.3 
@@ -32,15 +130,21 @@ pub struct JkmShortestPathMap {
 	
 	// line: (x, y, y2) where y < y2
 	// obstacle: (x, y, w, h)
-	fn v_line_touches_obstacle (line:(f64, f64, f64), obstacle: (f64, f64, f64, f64) ) -> bool {
+	fn v_line_touches_obstacle<S: Scalar> (line:(S, S, S), obstacle: (S, S, S, S) ) -> bool {
 		debug_assert!(line.1 <= line.2, "Parameters must be in order. Got {} and {} in this order.", line.1, line.2);
 		line.1 < obstacle.1 + obstacle.3 && line.2 > obstacle.1  
 		&& line.0 >= obstacle.0 && line.0 <= obstacle.0 + obstacle.2 
 	}
 	
+	// obstacle: (x, y, w, h)
+	fn obstacle_contains_coordinate<S: Scalar> (obstacle: (S, S, S, S), x: S, y: S) -> bool {
+		obstacle.0 < x && obstacle.0 + obstacle.2 > x
+		&& obstacle.1 < y && obstacle.1 + obstacle.3 > y
+	}
+	
 	// line: (x, y, x2)
 	// obstacle: (x, y, w, h)
-	fn h_line_overlaps_obstacle (line:(f64, f64, f64), obstacle: (f64, f64, f64, f64) ) -> bool {
+	fn h_line_overlaps_obstacle<S: Scalar> (line:(S, S, S), obstacle: (S, S, S, S) ) -> bool {
 		debug_assert!(line.0 <= line.2, "Parameters must be in order. Got {} and {} in this order.", line.0, line.1);
 		line.0 < obstacle.0 + obstacle.2 && line.2 > obstacle.0  
 		&& line.1 > obstacle.1 && line.1 < obstacle.1 + obstacle.3 
@@ -48,13 +152,13 @@ pub struct JkmShortestPathMap {
 	
 	// line: (x, y, y2) where y < y2
 	// obstacle: (x, y, w, h)
-	fn v_line_overlaps_obstacle (line:(f64, f64, f64), obstacle: (f64, f64, f64, f64) ) -> bool {
+	fn v_line_overlaps_obstacle<S: Scalar> (line:(S, S, S), obstacle: (S, S, S, S) ) -> bool {
 		debug_assert!(line.1 <= line.2, "Parameters must be in order. Got {} and {} in this order.", line.1, line.2);
 		line.1 < obstacle.1 + obstacle.3 && line.2 > obstacle.1  
 		&& line.0 > obstacle.0 && line.0 < obstacle.0 + obstacle.2 
 	}
 
-impl JkmShortestPathMap {
+impl<S: Scalar> JkmShortestPathMap<S> {
 
 	/// Create graph with a starting point, a destination and a rectangular map.
 	/// The graph will contain between  6 and 16 vertices after the creation, all connected in a grid
@@ -64,9 +168,9 @@ impl JkmShortestPathMap {
 	/// #Panics 
 	/// Panics when the start point is identical with the end point or if they are not both within the map.
 	
-	pub fn new (start: (f64, f64), end: (f64, f64), map: (f64,f64,f64,f64)) -> JkmShortestPathMap {
+	pub fn new (start: (S, S), end: (S, S), map: (S,S,S,S)) -> JkmShortestPathMap<S> {
 		
-		let mut g = Vec::new();
+		let mut g = NodeStore::new();
 		g.push(Box::new(GraphNode::new(map.0, map.1)));
 		g.push(Box::new(GraphNode::new(map.0 + map.2, map.1)));
 		g.push(Box::new(GraphNode::new(map.0 + map.2, map.1 + map.3)));
@@ -89,6 +193,23 @@ impl JkmShortestPathMap {
 			map: map, 
 			dead_nodes: BinaryHeap::new(),
 			update_root: Vec::new(), 
+			history: History::new(),
+			timetable: Timetable::new(),
+			zones: Vec::new(),
+			portals: Vec::new(),
+			layers: vec![Layer::new(map)],
+			layer_blockers: Vec::new(),
+			hierarchy: None,
+			border: [BorderMode::Open; 4],
+			touch: TouchPolicy::Open,
+			seams: Seams::new(),
+			clearance_tracked: false,
+			changed_obstacles: Vec::new(),
+			tracked_paths: None,
+			change_log: ChangeLog::new(),
+			counters: Counters::default(),
+			edges: EdgeIndex::new(map),
+			next_region: regions::DESTINATION_REGION + 1,
 		};
 	
 		if start.1 == map.1 && end.1 == map.1+map.3 && end.0 == start.0{
@@ -101,7 +222,7 @@ impl JkmShortestPathMap {
 				obj.end_point_index = ei;
 				obj.invalidate_paths_through_node(0);
 				obj.graph[si].shortest_path = Some(SOUTH);
-				obj.graph[ei].cost = 0.0;
+				obj.graph[ei].cost = S::ZERO;
 				obj.graph[ei].shortest_path = None;
 				obj.update_neighbours(ei);
 		}
@@ -115,7 +236,7 @@ impl JkmShortestPathMap {
 				obj.end_point_index = ei;
 				obj.invalidate_paths_through_node(0);
 				obj.graph[si].shortest_path = Some(EAST);
-				obj.graph[ei].cost = 0.0;
+				obj.graph[ei].cost = S::ZERO;
 				obj.graph[ei].shortest_path = None;
 				obj.update_neighbours(ei);
 		}
@@ -127,6 +248,7 @@ impl JkmShortestPathMap {
 			let ei = obj.graph.len();
 			obj.end_point_index = ei;
 			obj.graph.push(Box::new(GraphNode::new(end.0, end.1)));
+			obj.graph[ei].cost = S::ZERO;
 			
 			if start.1 < end.1 {
 				obj.graph[si].cost = end.1 - start.1;
@@ -160,6 +282,7 @@ impl JkmShortestPathMap {
 			let ei = obj.graph.len();
 			obj.end_point_index = ei;
 			obj.graph.push(Box::new(GraphNode::new(end.0, end.1)));
+			obj.graph[ei].cost = S::ZERO;
 			
 			if start.0 < end.0 {
 				obj.graph[si].cost = end.0 - start.0;
@@ -198,35 +321,47 @@ impl JkmShortestPathMap {
 			obj.start_point_index = start_index;
 			obj.end_point_index = end_index;
 			obj.invalidate_paths_through_node(0);
-			obj.graph[end_index].cost = 0.0;
+			obj.graph[end_index].cost = S::ZERO;
 			obj.graph[end_index].shortest_path = None;
 			obj.update_neighbours(end_index);
 			
 		}
+		obj.after_update();
 		obj
 	}
 	
 	/// Reads out the coordinates of the end_point_index
-	pub fn get_destination_coordinates(&self) -> (f64, f64) {
+	pub fn get_destination_coordinates(&self) -> (S, S) {
 		(self.graph[self.end_point_index].x, self.graph[self.end_point_index].y)
 	}
 	
 	/// Adds a rectangular obstacle to the map and changes the graph's nodes and shortest paths accordingly. 
 	/// Note that two obstacles that are exactly aligned will not block the way between them. There must be 
 	/// an overlapping to disable paths between obstacles.
-	pub fn insert_obstacle (&mut self, x: f64, y: f64, w: f64, h: f64) {
+	pub fn insert_obstacle (&mut self, x: S, y: S, w: S, h: S) {
+		self.add_obstacle(x,y,w,h);
+		self.after_update();
+	}
+	
+	// Same as insert_obstacle() but without after_update(), so that several changes can be applied before it
+	fn add_obstacle (&mut self, x: S, y: S, w: S, h: S) {
 		//add obstacle to list
-		self.obstacles.push((x,y,w,h));
-		
+		self.push_obstacle((x,y,w,h));
+		self.cut_obstacle(x, y, w, h);
+	}
+	
+	// Changes the graph around an obstacle that is already in the list of obstacles, the shortest paths are repaired
+	fn cut_obstacle (&mut self, x: S, y: S, w: S, h: S) {
 		// check map boundaries:
 		if x > self.map.0 + self.map.2 || y > self.map.1 + self.map.3 
 			{ return; }
-		let w = if x + w > self.map.0 + self.map.2 { self.map.0 + self.map.2 - x + 4.0 * EPS} else { w };
-		let h = if y + h > self.map.1 + self.map.3 { self.map.1 + self.map.3  - y + 4.0 * EPS} else { h };
+		let w = if x + w > self.map.0 + self.map.2 { self.map.0 + self.map.2 - x + S::from_f64(4.0) * S::EPS} else { w };
+		let h = if y + h > self.map.1 + self.map.3 { self.map.1 + self.map.3  - y + S::from_f64(4.0) * S::EPS} else { h };
+		let mut lap = self.counters.start();
 		
 		// Find all edges going thorugh the new obstacle
-		let mut h_blocked: BinaryHeap<MinSortableEdge> = BinaryHeap::new();
-		let mut v_blocked: BinaryHeap<MinSortableEdge> = BinaryHeap::new();
+		let mut h_blocked: BinaryHeap<MinSortableEdge<S>> = BinaryHeap::new();
+		let mut v_blocked: BinaryHeap<MinSortableEdge<S>> = BinaryHeap::new();
 		let mut nodes_to_erase = Vec::new();
 		
 		for v0 in 0..self.graph.len() {
@@ -305,6 +440,7 @@ impl JkmShortestPathMap {
 		for &node in nodes_to_erase.iter() {
 			self.erase_node(node);
 		}
+		let cut = stats::lap(&mut lap);
 		
 		// Create new vertices and connect them in a circle
 		// While doing so, also make a connection to the rest of the graph:
@@ -345,7 +481,7 @@ impl JkmShortestPathMap {
 			let index = self.graph.len();
 			if let Some(edge) = v_blocked.pop() {
 				let (_, top) = edge.0;
-				let cross_x:f64 = edge.1;
+				let cross_x:S = edge.1;
 				let mut new_node = Box::new(GraphNode::new(cross_x, y));
 				if !self.coordinate_is_blocked(new_node.x, new_node.y) {
 					if self.graph[top].y < y { 		
@@ -389,7 +525,7 @@ impl JkmShortestPathMap {
 		    let index = self.graph.len();
 			if let Some(edge) = h_blocked.pop(){
 				let (_, right) = edge.0;
-				let cross_y:f64 = edge.1;
+				let cross_y:S = edge.1;
 				let mut new_node = Box::new(GraphNode::new(x+w, cross_y));			
 				if !self.coordinate_is_blocked(new_node.x, new_node.y) {
 					if self.graph[right].x > x + w {
@@ -435,7 +571,7 @@ impl JkmShortestPathMap {
 			if let Some(edge) = v_blocked_buf.pop(){
 				let (bot, _) = edge.0;
 				//at this point, bot could be erased, therefore cleanup() is needed
-				let cross_x:f64 = edge.1;
+				let cross_x:S = edge.1;
 				let mut new_node = Box::new(GraphNode::new(cross_x, y+h));		
 				if !self.coordinate_is_blocked(new_node.x, new_node.y) {
 					if self.graph[bot].y > y + h {
@@ -478,7 +614,7 @@ impl JkmShortestPathMap {
 			let index = self.graph.len();
 			if let Some(edge) = h_blocked_buf.pop(){
 				let (left, _) = edge.0;
-				let cross_y:f64 = edge.1;
+				let cross_y:S = edge.1;
 				let mut new_node = Box::new(GraphNode::new(x, cross_y));	
 				if !self.coordinate_is_blocked(new_node.x, new_node.y) {
 					if self.graph[left].x < x {
@@ -511,47 +647,53 @@ impl JkmShortestPathMap {
 				}
 			}
 		}
-		
+		let build = stats::lap(&mut lap);
 		
 		self.cleanup();
 		self.update();
 		self.erase_lonely_nodes();
 		
 		// Search the node closest to the end point, then inititate recomputation starting from this node
-		let mut closest_node = (None, std::f64::INFINITY);
+		let mut closest_node = (None, S::INFINITY);
 		for j in i..self.graph.len() {
 			if self.graph[j].cost < closest_node.1 {
 				closest_node = (Some(j), self.graph[j].cost);
 			}
 		}
-		if let (Some(closest),_) = closest_node {
-			//self.update_node(closest);
-			//self.update_neighbours(closest); //This alone does not work, since it will not always update all nodes
-			let added_nodes = index - i;
-			for k in 0..(added_nodes) {
-				let mut to_update = if k%2 == 0 {closest + ((k+1)/2)} else {closest - ((k+1)/2)};
-				loop {
-					to_update = if to_update >= (i + added_nodes) { to_update-added_nodes }
-									else if to_update < i { to_update + added_nodes }
-									else {break};
-				}
-				debug_assert!(to_update <self.graph.len() && to_update >= i, "Boundry violated: to_update: {}, i:{}, v:{}, ho:{}", to_update, i, v, ho);
-				self.update_node(to_update);
-				self.update_neighbours(to_update);
-			}	
-		}//else: not connected at all => no update possible	
+		// if no new node has a path yet, they can still get one from the nodes they are linked to
+		let closest = closest_node.0.unwrap_or(i);
+		//self.update_node(closest);
+		//self.update_neighbours(closest); //This alone does not work, since it will not always update all nodes
+		let added_nodes = index - i;
+		for k in 0..(added_nodes) {
+			let mut to_update = if k%2 == 0 {closest + ((k+1)/2)} else {closest + added_nodes - ((k+1)/2)};
+			loop {
+				to_update = if to_update >= (i + added_nodes) { to_update-added_nodes }
+								else if to_update < i { to_update + added_nodes }
+								else {break};
+			}
+			debug_assert!(to_update <self.graph.len() && to_update >= i, "Boundry violated: to_update: {}, i:{}, v:{}, ho:{}", to_update, i, v, ho);
+			self.update_node(to_update);
+			self.update_neighbours(to_update);
+		}
 		
 		// At this point, the graph should be consistent again
+		let repair = stats::lap(&mut lap);
 	
 		// This call is only here to shrink the graph size and get rid of unecessary nodes
 		// It is best called in the end because the recomputtation takes advantage of the fact that all 
 		//  new nodes have been appended to the end of the vector. Calling this function may destroy that order.
+		self.seal_touching((x,y,w,h));
 		self.swap_out_dead_nodes();
+		let shrink = stats::lap(&mut lap);
+		self.counters.current.insert_phases = InsertPhases { cut, build, repair, shrink };
 	}
 	
 	/// Removes an obstacle that was instered earlier. 
 	/// This function will do noting if there was no such obstacle inserted or if it was alread removed.
-	pub fn remove_obstacle (&mut self, x: f64, y: f64, w: f64, h: f64) {
+	/// The space between the layers of a map created with new_layered() can not be removed.
+	pub fn remove_obstacle (&mut self, x: S, y: S, w: S, h: S) {
+		if self.layer_blockers.contains(&(x, y, w, h)) { return; }
 		let mut obstacle_index = None;
 		for (i, &(ox, oy, ow, oh)) in self.obstacles.iter().enumerate() {
 			if ox == x && oy == y && oh == h && ow == w {
@@ -560,7 +702,7 @@ impl JkmShortestPathMap {
 			}
 		}
 		if let Some(i) = obstacle_index {
-			self.obstacles.swap_remove(i);
+			self.swap_remove_obstacle(i);
 			for i in 0..self.graph.len() {
 				if self.graph[i].x > x && self.graph[i].x < x+w {
 					// upper line
@@ -591,17 +733,29 @@ impl JkmShortestPathMap {
 					}
 				}
 			}
+			self.after_update();
 		}
 	}
 	
-	/// Makes the border of the map blocking, i.e. no paths can go through it
+	/// Makes the border of the map blocking, i.e. no paths can go through it. Same as closing all sides with set_border().
 	pub fn add_map_border(&mut self) {
-		let d = 0.0625;
-		let (x,y,w,h) = self.map;
-		self.obstacles.push( (x-d, y, d, h) );
-		self.obstacles.push( (x+w, y, d, h) );
-		self.obstacles.push( (x, y-d, w, d) );
-		self.obstacles.push( (x, y+h, w, d) );
+		let closed = BorderMode::Closed(S::from_f64(DEFAULT_BORDER_THICKNESS).max(S::EPS));
+		self.set_border(closed, closed, closed, closed);
+	}
+	
+	/// Computes how the path from the start point would change if the given obstacle was inserted, without changing the map.
+	/// If the current shortest path from the start point does not go through the obstacle, the result is found without 
+	/// computing anything new. Otherwise only the nodes whose path goes through the obstacle are searched again, 
+	/// with temporary nodes around the obstacle.
+	pub fn would_block(&self, x: S, y: S, w: S, h: S) -> PlacementImpact<S> {
+		let cost_before = self.graph[self.start_point_index].cost;
+		let cost_after = if cost_before == S::INFINITY { cost_before }
+			else { Overlay::blocking(self, (x,y,w,h)).cost_with_blocker(self, self.start_point_index) };
+		PlacementImpact {
+			start_reachable: cost_after < S::INFINITY,
+			cost_before,
+			cost_after,
+		}
 	}
 	
 	/// Returns the nearest checkpoint on the shortest path from the given coordinate to the destination. 
@@ -610,104 +764,73 @@ impl JkmShortestPathMap {
 	/// If the coordinate is most likely already on a node, call next_checkpoint() instead.
 	/// Returns None if there is no path to the destination. 
 	///	If the destination is already reached, its coordinates are returned.
-	pub fn nearest_checkpoint(&self, x: f64, y: f64) -> Option<(f64,f64)> {
-		let destination = self.end_point_index;
-		if self.graph[destination].x == x && self.graph[destination].y == y {
-			return Some((x,y));
-		}
-		
-		let mut nearest = (None, std::f64::INFINITY, std::f64::INFINITY);
-		
-		for node in self.graph.iter() {
-			if let Some(right_index) = node.neighbours[EAST] {
-				let right = &self.graph[right_index];
-				if node.x <= x && right.x >= x {
-					let new_y = node.y;
-					let total_cost;
-					let cost_to_edge = (y-new_y).abs();
-					let cost_on_edge = if (node.cost + x - node.x ) < (right.cost + right.x - x)
-										    {total_cost = node.cost + x - node.x + cost_to_edge; x - node.x}
-									   else {total_cost = right.cost + right.x - x + cost_to_edge; right.x - x};
-					
-					if total_cost <= nearest.1 && cost_on_edge + cost_to_edge > EPS 
-						&& (total_cost < nearest.1 
-							|| (cost_on_edge + cost_to_edge < nearest.2 && total_cost < std::f64::INFINITY )
-						) {
-						if (y < new_y && self.v_line_overlaps_no_obstacle(x, y, new_y ))
-							||( y > new_y && self.v_line_overlaps_no_obstacle(x, new_y, y )){
-							nearest = (Some((x,new_y)), total_cost, cost_on_edge + cost_to_edge);
-						}		
-					} 	
-				}				
-			}
-			if let Some(bot_index) = node.neighbours[SOUTH] {
-				let bot = &self.graph[bot_index];
-				if node.y <= y && bot.y >= y {
-					let new_x = node.x;
-					let total_cost;
-					let cost_to_edge = (x-new_x).abs();
-					let cost_on_edge = if (node.cost + y - node.y ) < (bot.cost + bot.y - y)
-										    {total_cost = node.cost + y - node.y + cost_to_edge; y - node.y}
-									   else {total_cost = bot.cost + bot.y - y + cost_to_edge; bot.y - y};
-					if total_cost <= nearest.1  && cost_on_edge + cost_to_edge > EPS 
-						&& (total_cost < nearest.1 
-							 || (cost_on_edge + cost_to_edge < nearest.2 && total_cost < std::f64::INFINITY  )
-							) {
-						if (x < new_x && self.h_line_overlaps_no_obstacle(x, y, new_x ))
-							|| (x > new_x && self.h_line_overlaps_no_obstacle(new_x, y, x )){
-							nearest = (Some((new_x,y)), total_cost, cost_on_edge + cost_to_edge);
-						}		
-					} 	
-				}				
-			}
-		}
-		nearest.0
+	pub fn nearest_checkpoint(&self, x: S, y: S) -> Option<(S,S)> {
+		query::nearest_checkpoint(self, x, y)
 	}
 	
 	/// Returns the next checkpoint on the shortest path from the given coordinate to the destination, 
 	///  assuming that the given coordinate is a checkpoint given earlier. In case it is not, it will still
 	///  find the correct result, however it is inefficient to use this function then.
 	/// If the coordinate is most likely between nodes, call nearest_checkpoint() instead.
+	/// If the path continues through a portal, the other end of the portal is returned, call next_step() to tell this apart.
 	/// Returns None if there is no path to the destination. 
 	///	If the destination is already reached, its coordinates are returned.
-	pub fn next_checkpoint(&self, x: f64, y: f64) -> Option<(f64,f64)> {
-
-		let destination = self.end_point_index;
-		if self.graph[destination].x == x && self.graph[destination].y == y {
-			return Some((x,y));
+	pub fn next_checkpoint(&self, x: S, y: S) -> Option<(S,S)> {
+		query::next_checkpoint(self, x, y)
+	}
+	
+	// The next node on the shortest path from n to the destination
+	fn next_node(&self, n: usize) -> Option<usize> {
+		match self.graph[n].shortest_path {
+			Some(PORTAL) => self.graph[n].portal.map(|end| end.other),
+			Some(sp) => self.graph[n].neighbours[sp],
+			None => None,
 		}
-		
-		for node in self.graph.iter() {
-			if (node.x - x).abs() < EPS && (node.y - y).abs() < EPS {
-				if let Some(sp) = node.shortest_path {
-					if let Some(neighbour) = node.neighbours[sp] {
-						return Some( (self.graph[neighbour].x, self.graph[neighbour].y) );
-					}
-				}
-				break;
-			}
+	}
+	
+	// Has to be called at the end of every public function that changes the graph
+	fn after_update(&mut self) {
+		let started = self.counters.start();
+		if !self.portals.is_empty() {
+			self.restore_portals();
 		}
-		//println!("No current node found.");
-		self.nearest_checkpoint(x,y)
+		if !self.zones.is_empty() {
+			let touched = self.graph.touched().to_vec();
+			self.reroute_against_zones(&touched);
+			self.split_at_zones(&touched);
+		}
+		self.track_managed_nodes();
+		let touched = self.graph.take_touched();
+		self.update_edge_index(&touched);
+		self.label_regions(&touched);
+		self.update_clearance();
+		// the labels and the clearance do not count as changes
+		self.graph.take_touched();
+		self.update_hierarchy();
+		self.counters.finish_update(started);
 	}
 	
 	// Checks recursivly on neighbours wether their shortest path goes through the given node.
 	// All these paths are invalidated, however no edges are deleted.
 	fn invalidate_paths_through_node (&mut self, n: usize) {	
-		for direction in 0..4 {
-			if let Some(i) = self.graph[n].neighbours[direction] {
-				if let Some(sp_of_neighbour) = self.graph[i].shortest_path {
-					if self.graph[i].neighbours[sp_of_neighbour] == Some(n) {
-						self.invalidate_paths_through_node (i);
-					}
-					else {
-						self.consider_node_as_update_root(i);
-					}
+		self.counters.enter_invalidation();
+		let portal = self.graph[n].portal.and_then(|end| if end.incoming { Some(end.other) } else { None });
+		let neighbours = self.graph[n].neighbours;
+		for &neighbour in neighbours.iter().chain(Some(&portal)) {
+			if let Some(i) = neighbour {
+				if self.next_node(i) == Some(n) {
+					self.invalidate_paths_through_node (i);
+				}
+				// the destination has no path, but its cost is still where the update can start from
+				else if self.graph[i].cost < S::INFINITY {
+					self.consider_node_as_update_root(i);
 				}
 			}
 		}
 		self.graph[n].shortest_path = None;
-		self.graph[n].cost = std::f64::INFINITY;
+		self.graph[n].cost = S::INFINITY;
+		self.note_path_change(n);
+		self.counters.leave_invalidation();
 	}
 	
 	fn consider_node_as_update_root(&mut self, n: usize) {
@@ -724,31 +847,74 @@ impl JkmShortestPathMap {
 		
 	}
 	
+	// Computes all shortest paths from scratch with Dijkstra's algorithm, only nodes whose path changes are written
+	fn recompute_paths(&mut self) {
+		let (costs, paths) = self.dijkstra();
+		for n in 0..self.graph.len() {
+			if self.graph[n].cost != costs[n] || self.graph[n].shortest_path != paths[n] {
+				self.graph[n].cost = costs[n];
+				self.graph[n].shortest_path = paths[n];
+				self.note_path_change(n);
+			}
+		}
+	}
+	
+	// Cost and direction of the shortest path of each node, found by Dijkstra's algorithm without looking at the stored paths
+	pub(crate) fn dijkstra(&self) -> (Vec<S>, Vec<Option<usize>>) {
+		let mut costs = vec![S::INFINITY; self.graph.len()];
+		let mut paths = vec![None; self.graph.len()];
+		let mut queue = BinaryHeap::new();
+		costs[self.end_point_index] = S::ZERO;
+		queue.push(MinSortableNode(self.end_point_index, S::ZERO));
+		while let Some(MinSortableNode(n, cost)) = queue.pop() {
+			if cost > costs[n] { continue; }
+			for direction in 0..4 {
+				if let Some(neighbour) = self.graph[n].neighbours[direction] {
+					let back = (direction + 2) % 4;
+					let new_cost = cost + self.distance_on_map(n, neighbour);
+					if new_cost < costs[neighbour] && self.can_move(neighbour, back) {
+						costs[neighbour] = new_cost;
+						paths[neighbour] = Some(back);
+						queue.push(MinSortableNode(neighbour, new_cost));
+					}
+				}
+			}
+			if let Some(end) = self.graph[n].portal {
+				if end.incoming && cost + end.cost < costs[end.other] {
+					costs[end.other] = cost + end.cost;
+					paths[end.other] = Some(PORTAL);
+					queue.push(MinSortableNode(end.other, cost + end.cost));
+				}
+			}
+		}
+		(costs, paths)
+	}
+	
 	// line: (x, y, x2)
 	// obstacle: (x, y, w, h)
-	fn h_line_overlaps_no_obstacle (&self, x0: f64, y: f64, x1: f64) -> bool {
+	fn h_line_overlaps_no_obstacle (&self, x0: S, y: S, x1: S) -> bool {
 		for &o in self.obstacles.iter() {
 			if h_line_overlaps_obstacle ( (x0,y,x1), o ) { return false; }
 		}
-		true
+		self.touch == TouchPolicy::Open || !self.seams.seal_h_line(x0, y, x1)
 	}
 	
 	// line: (x, y, y2)
 	// obstacle: (x, y, w, h)
-	fn v_line_overlaps_no_obstacle (&self, x: f64, y0: f64, y1: f64) -> bool {
+	fn v_line_overlaps_no_obstacle (&self, x: S, y0: S, y1: S) -> bool {
 		for &o in self.obstacles.iter() {
 			if v_line_overlaps_obstacle ( (x,y0,y1), o ) { return false; }
 		}
-		true
+		self.touch == TouchPolicy::Open || !self.seams.seal_v_line(x, y0, y1)
 	}
 	
-	fn coordinate_is_blocked(&mut self, x: f64, y: f64) -> bool {
-		for obs in self.obstacles.iter() {
-			if obs.0 < x && obs.0 + obs.2 > x
-				&& obs.1 < y && obs.1 + obs.3 > y
-				{ return true; }
+	/// Checks whether the coordinate lies strictly inside of an obstacle.
+	/// With TouchPolicy::Closed, it is also blocked between two touching obstacles, see set_touch_policy().
+	pub(crate) fn coordinate_is_blocked(&self, x: S, y: S) -> bool {
+		for &obs in self.obstacles.iter() {
+			if obstacle_contains_coordinate(obs, x, y) { return true; }
 		}
-		false
+		self.touch == TouchPolicy::Closed && self.seams.seal_coordinate(x, y)
 	}
 	
 	/// Connects two nodes horizontally if possible and updates shortest paths that are changed by this new edge
@@ -760,14 +926,16 @@ impl JkmShortestPathMap {
 			self.graph[left].neighbours[EAST] = Some(right);
 			self.graph[right].neighbours[WEST] = Some(left);
 			
-			if self.graph[left].cost + cost < self.graph[right].cost {
+			if self.graph[left].cost + cost < self.graph[right].cost && self.can_move(right, WEST) {
 				self.graph[right].shortest_path = Some(WEST);
 				self.graph[right].cost = self.graph[left].cost + cost;
+				self.note_path_change(right);
 				self.update_neighbours(right);
 			}
-			else if self.graph[right].cost + cost < self.graph[left].cost {
+			else if self.graph[right].cost + cost < self.graph[left].cost && self.can_move(left, EAST) {
 				self.graph[left].shortest_path = Some(EAST);
 				self.graph[left].cost = self.graph[right].cost + cost;
+				self.note_path_change(left);
 				self.update_neighbours(left);
 			}
 			true
@@ -783,14 +951,16 @@ impl JkmShortestPathMap {
 			self.graph[top].neighbours[SOUTH] = Some(bot);
 			self.graph[bot].neighbours[NORTH] = Some(top);
 			
-			if self.graph[top].cost + cost < self.graph[bot].cost {
+			if self.graph[top].cost + cost < self.graph[bot].cost && self.can_move(bot, NORTH) {
 				self.graph[bot].shortest_path = Some(NORTH);
 				self.graph[bot].cost = self.graph[top].cost + cost;
+				self.note_path_change(bot);
 				self.update_neighbours(bot);
 			}
-			else if self.graph[bot].cost + cost < self.graph[top].cost {
+			else if self.graph[bot].cost + cost < self.graph[top].cost && self.can_move(top, SOUTH) {
 				self.graph[top].shortest_path = Some(SOUTH);
 				self.graph[top].cost = self.graph[bot].cost + cost;
+				self.note_path_change(top);
 				self.update_neighbours(top);
 			}
 			true
@@ -801,39 +971,60 @@ impl JkmShortestPathMap {
 	// Call this after adding a node / edge
 	// Checks all neighbours if they could have a shorter path when using this path
 	fn update_neighbours (&mut self, n: usize) {
+		self.counters.enter_update();
 		for j in 0..4 {
 			if let Some(i) = self.graph[n].neighbours[j] {
 				let cost = (self.graph[n].x - self.graph[i].x).abs() + (self.graph[n].y - self.graph[i].y).abs();
-				if self.graph[n].cost + cost < self.graph[i].cost {
+				if self.graph[n].cost + cost < self.graph[i].cost && self.can_move(i, (j+2)%4) {
 					self.graph[i].shortest_path = Some((j+2)%4);
 					self.graph[i].cost = self.graph[n].cost + cost;
+					self.note_path_change(i);
+					self.counters.relax();
 					self.update_neighbours(i);
 				}
 			}
 		}
+		if let Some(end) = self.graph[n].portal {
+			if end.incoming && self.graph[n].cost + end.cost < self.graph[end.other].cost {
+				self.graph[end.other].shortest_path = Some(PORTAL);
+				self.graph[end.other].cost = self.graph[n].cost + end.cost;
+				self.note_path_change(end.other);
+				self.counters.relax();
+				self.update_neighbours(end.other);
+			}
+		}
+		self.counters.leave_update();
 	}
 	
 	/// Update a specific node's shortest path by looking at all neighbours that exist
 	/// This function can be (and is) called while the node has connection to neighbours that have not been added to the graph yet
 	fn update_node (&mut self, n: usize) {
 		if n == self.end_point_index {return;}
-		let mut result = (None, std::f64::INFINITY); // Old value does not need to be considered, if this way is still available we will find it anyway
+		let mut result = (None, S::INFINITY); // Old value does not need to be considered, if this way is still available we will find it anyway
 		let graph_size = self.graph.len();
 		for i in 0..4 {
 			if let Some(neighbour) = self.graph[n].neighbours[i] {
 				if neighbour < graph_size // check whether the node exists
 				{
 					let cost = self.graph[neighbour].cost + self.distance_on_map(n, neighbour);
-					if cost < result.1 { result = (Some(i), cost); }
+					if cost < result.1 && self.can_move(n, i) { result = (Some(i), cost); }
 				}
 			}
 			
 		}
-		self.graph[n].shortest_path = result.0;
-		self.graph[n].cost = result.1;
+		if let Some(end) = self.graph[n].portal {
+			if end.outgoing && end.other < graph_size && self.graph[end.other].cost + end.cost < result.1 {
+				result = (Some(PORTAL), self.graph[end.other].cost + end.cost);
+			}
+		}
+		if self.graph[n].shortest_path != result.0 || self.graph[n].cost != result.1 {
+			self.graph[n].shortest_path = result.0;
+			self.graph[n].cost = result.1;
+			self.note_path_change(n);
+		}
 	}
 	
-	fn distance_on_map(&self, n0: usize, n1: usize) -> f64 {
+	fn distance_on_map(&self, n0: usize, n1: usize) -> S {
 		(self.graph[n0].x - self.graph[n1].x).abs() + (self.graph[n0].y - self.graph[n1].y).abs() 
 	}
 	
@@ -841,7 +1032,7 @@ impl JkmShortestPathMap {
 	// The coordinate has to be given absolute, not relative to the node n 
 	// and can be an x- or y-coordinate, depending on the direction.
 	// No new edges are inserted in this function except fot the two who replace the one to split up.
-	fn split_edge(&mut self, n: usize, direction: usize, coordinate: f64) -> usize {
+	fn split_edge(&mut self, n: usize, direction: usize, coordinate: S) -> usize {
 		debug_assert!(self.graph[n].neighbours[direction].is_some());
 		debug_assert!(
 			(direction == NORTH && self.graph[n].y > coordinate && self.graph[self.graph[n].neighbours[direction].unwrap()].y < coordinate)
@@ -871,7 +1062,7 @@ impl JkmShortestPathMap {
 	
 	// panics if the node cannot be merged
 	// a node can be merged if it has exactly two neighbouts which are in the opposite direction
-	// Unless n is the last node in the graph, this funciton will produce unused nodes within the graph (no neighbours, coordinate std::f64::NEG_INFINITY|std::f64::NEG_INFINITY)
+	// Unless n is the last node in the graph, this funciton will produce unused nodes within the graph (no neighbours, coordinate S::NEG_INFINITY|S::NEG_INFINITY)
 	fn merge_node(&mut self, n: usize) {
 		if let Some(top) = self.graph[n].neighbours[NORTH] {
 			if let Some(bot) = self.graph[n].neighbours[SOUTH] {
@@ -899,12 +1090,14 @@ impl JkmShortestPathMap {
 		self.cleanup();
 	}
 	
-	// Detatches the node from the graph and moves it to std::f64::NEG_INFINITY | std::f64::NEG_INFINITY
+	// Detatches the node from the graph and moves it to S::NEG_INFINITY | S::NEG_INFINITY
 	// The node can't be deleted since that would change the index of other nodes
 	// TODO: Make the node slots available for new nodes
 	fn erase_node (&mut self, n: usize) {
-		self.graph[n].x = std::f64::NEG_INFINITY;
-		self.graph[n].y = std::f64::NEG_INFINITY;
+		self.note_path_change(n);
+		self.detach_portal(n);
+		self.graph[n].x = S::NEG_INFINITY;
+		self.graph[n].y = S::NEG_INFINITY;
 		self.invalidate_paths_through_node(n);
 		for direction in 0..4 {
 			if let Some(neighbour) = self.graph[n].neighbours[direction] {
@@ -913,7 +1106,7 @@ impl JkmShortestPathMap {
 				self.graph[neighbour].neighbours[other_direction] = None;
 			}
 		}
-		self.graph[n] = Box::new(GraphNode::new(std::f64::NEG_INFINITY, std::f64::NEG_INFINITY));
+		*self.graph[n] = GraphNode::new(S::NEG_INFINITY, S::NEG_INFINITY);
 		self.dead_nodes.push(n);
 	}
 	
@@ -925,13 +1118,26 @@ impl JkmShortestPathMap {
 				&& !self.graph[i].neighbours[SOUTH].is_some()
 				&& !self.graph[i].neighbours[WEST].is_some() 
 			{
-				self.graph[i].x = std::f64::NEG_INFINITY;
-				self.graph[i].y = std::f64::NEG_INFINITY;
+				self.detach_portal(i);
+				self.graph[i].x = S::NEG_INFINITY;
+				self.graph[i].y = S::NEG_INFINITY;
 				self.dead_nodes.push(i);
 			}
 		}
 	}
 	
+	// Erases all nodes outside of the map. Obstacles that stick out of the map on the left or on the top
+	// have nodes around them that would connect the parts of the map they separate.
+	pub(crate) fn erase_nodes_outside_map (&mut self) {
+		let (mx, my, mw, mh) = self.map;
+		for n in 0..self.graph.len() {
+			let (x, y) = (self.graph[n].x, self.graph[n].y);
+			if x != S::NEG_INFINITY && (x < mx || x > mx + mw || y < my || y > my + mh) {
+				self.erase_node(n);
+			}
+		}
+	}
+	
 	// Takes the last nodes in the graph and swaps them with the dead nodes so those can be erased
 	fn swap_out_dead_nodes (&mut self) {
 		let mut last = None;
@@ -941,7 +1147,7 @@ impl JkmShortestPathMap {
 				if last_node == dead_slot {continue;}
 			}
 			last = Some(dead_slot);
-			debug_assert!(self.graph[dead_slot].x == std::f64::NEG_INFINITY && self.graph[dead_slot].y == std::f64::NEG_INFINITY , "Node #{} that was listed as dead was alive! It had the coordinates [{}|{}] ! ", dead_slot, self.graph[dead_slot].x, self.graph[dead_slot].y );
+			debug_assert!(self.graph[dead_slot].x == S::NEG_INFINITY && self.graph[dead_slot].y == S::NEG_INFINITY , "Node #{} that was listed as dead was alive! It had the coordinates [{}|{}] ! ", dead_slot, self.graph[dead_slot].x, self.graph[dead_slot].y );
 			debug_assert!(self.graph[dead_slot].neighbours == [None, None, None, None], "Node #{} that was listed as dead was alive! It had some neighbours! ", dead_slot );
 			if dead_slot == self.graph.len() - 1 { self.graph.pop(); }  // Note: since dead_nodes is a Max-Heap, this if condition is either true or the last node is alive
 			else {
@@ -952,6 +1158,12 @@ impl JkmShortestPathMap {
 							self.graph[neighbour].neighbours[ (direction + 2) %4 ] = Some(dead_slot);
 						}
 					}
+					if let Some(end) = node.portal {
+						if let Some(ref mut other_end) = self.graph[end.other].portal {
+							other_end.other = dead_slot;
+						}
+						self.move_portal_end(self.graph.len(), dead_slot);
+					}
 					self.graph[dead_slot] = node;
 				}
 				else {
@@ -986,7 +1198,7 @@ impl JkmShortestPathMap {
 		// Also we are not interested in edges that go vertically here
 		let x = self.graph[n].x;
 		let y = self.graph[n].y;
-		let mut new_node : Option<(usize,usize,f64)> = None;
+		let mut new_node : Option<(usize,usize,S)> = None;
 		for (i, node) in self.graph.iter().enumerate() {
 			if let Some(neighbour) = node.neighbours[EAST] {
 				if node.x <=  x && self.graph[neighbour].x >= x && node.y < y {
@@ -1018,7 +1230,7 @@ impl JkmShortestPathMap {
 		debug_assert!(self.graph[n].neighbours[EAST] == None);
 		let x = self.graph[n].x;
 		let y = self.graph[n].y;
-		let mut new_node : Option<(usize,usize,f64)> = None;
+		let mut new_node : Option<(usize,usize,S)> = None;
 		for (i, node) in self.graph.iter().enumerate() {
 			if let Some(neighbour) = node.neighbours[SOUTH] {
 				if node.y <=  y && self.graph[neighbour].y >= y && node.x > x {
@@ -1050,7 +1262,7 @@ impl JkmShortestPathMap {
 		debug_assert!(self.graph[n].neighbours[SOUTH] == None);
 		let x = self.graph[n].x;
 		let y = self.graph[n].y;
-		let mut new_node : Option<(usize,usize,f64)> = None;
+		let mut new_node : Option<(usize,usize,S)> = None;
 		for (i, node) in self.graph.iter().enumerate() {
 			if let Some(neighbour) = node.neighbours[EAST] {
 				if node.x <=  x && self.graph[neighbour].x >= x && node.y > y {
@@ -1082,7 +1294,7 @@ impl JkmShortestPathMap {
 		debug_assert!(self.graph[n].neighbours[WEST] == None);
 		let x = self.graph[n].x;
 		let y = self.graph[n].y;
-		let mut new_node : Option<(usize,usize,f64)> = None;
+		let mut new_node : Option<(usize,usize,S)> = None;
 		for (i, node) in self.graph.iter().enumerate() {
 			if let Some(neighbour) = node.neighbours[SOUTH] {
 				if node.y <=  y && self.graph[neighbour].y >= y && node.x < x {
@@ -1115,7 +1327,7 @@ impl JkmShortestPathMap {
 	fn cleanup(&mut self) {
 		let to_consider = self.dead_nodes.clone();
 		for &n in to_consider.iter() {
-			debug_assert!(self.graph[n].x == std::f64::NEG_INFINITY && self.graph[n].y == std::f64::NEG_INFINITY, "Dead node had a coordinate");
+			debug_assert!(self.graph[n].x == S::NEG_INFINITY && self.graph[n].y == S::NEG_INFINITY, "Dead node had a coordinate");
 			for direction in 0..4 {
 				if let Some(neighbour) = self.graph[n].neighbours[direction] {
 					self.graph[n].neighbours[direction] = None;
@@ -1137,7 +1349,7 @@ impl JkmShortestPathMap {
 	// These function search for a perfectly aligned neighbour node to connect, 
 	// they will ignore obstacles that are only touched on the border
 	fn reconnect_to_north(&mut self, n: usize) {
-		let mut closest = (None, std::f64::NEG_INFINITY);
+		let mut closest = (None, S::NEG_INFINITY);
 		let x = self.graph[n].x;
 		let y = self.graph[n].y;
 		for (i, node) in self.graph.iter().enumerate() {
@@ -1154,7 +1366,7 @@ impl JkmShortestPathMap {
 	}
 	
 	fn reconnect_to_east(&mut self, n: usize) {
-		let mut closest = (None, std::f64::INFINITY);
+		let mut closest = (None, S::INFINITY);
 		let x = self.graph[n].x;
 		let y = self.graph[n].y;
 		for (i, node) in self.graph.iter().enumerate() {
@@ -1171,7 +1383,7 @@ impl JkmShortestPathMap {
 	}
 	
 	fn reconnect_to_south(&mut self, n: usize) {
-	let mut closest = (None, std::f64::INFINITY);
+	let mut closest = (None, S::INFINITY);
 		let x = self.graph[n].x;
 		let y = self.graph[n].y;
 		for (i, node) in self.graph.iter().enumerate() {
@@ -1188,7 +1400,7 @@ impl JkmShortestPathMap {
 	}
 	
 	fn reconnect_to_west(&mut self, n: usize) {
-		let mut closest = (None, std::f64::NEG_INFINITY);
+		let mut closest = (None, S::NEG_INFINITY);
 		let x = self.graph[n].x;
 		let y = self.graph[n].y;
 		for (i, node) in self.graph.iter().enumerate() {
@@ -1208,15 +1420,35 @@ impl JkmShortestPathMap {
 
 
 
+impl<S: Scalar> PathGraph<S> for JkmShortestPathMap<S> {
+	fn node_count(&self) -> usize { self.graph.len() }
+	fn position(&self, n: usize) -> (S, S) { (self.graph[n].x, self.graph[n].y) }
+	fn cost(&self, n: usize) -> S { self.graph[n].cost }
+	fn neighbour(&self, n: usize, direction: usize) -> Option<usize> { self.graph[n].neighbours[direction] }
+	fn next_node(&self, n: usize) -> Option<usize> { self.next_node(n) }
+	fn destination(&self) -> usize { self.end_point_index }
+	fn h_line_is_free(&self, x0: S, y: S, x1: S) -> bool { self.h_line_overlaps_no_obstacle(x0, y, x1) }
+	fn v_line_is_free(&self, x: S, y0: S, y1: S) -> bool { self.v_line_overlaps_no_obstacle(x, y0, y1) }
+	fn move_is_allowed(&self, from: (S, S), to: (S, S)) -> bool { zones::move_is_allowed(&self.zones, from, to) }
+	fn is_jump(&self, n: usize) -> bool { self.graph[n].shortest_path == Some(PORTAL) }
+}
+
 /// Stores: 
 /// - An indices of each neighbour
 /// - The coordinate of the node
 /// - The shortest path's cost and the direction of the next node on this path
+/// - The connected region of the graph in which the node lies
+/// - The portal that starts or ends on the node, if there is one
+/// - The clearance of the edges to the north and east, together with the coordinate where the edge ended when it was computed
 /// The shortest path's cost is set to infinity if and only if it is invalid or unkown
-struct GraphNode {
+#[derive(Clone, Debug)]
+struct GraphNode<S> {
 	neighbours: [Option<usize>;4],
-	x: f64, y: f64, 
-	shortest_path: Option<usize>, cost: f64,
+	x: S, y: S, 
+	shortest_path: Option<usize>, cost: S,
+	region: usize,
+	portal: Option<PortalEnd<S>>,
+	clearance: [(S, S); 2],
 }
 
 const NORTH: usize = 0;
@@ -1224,12 +1456,15 @@ const EAST: usize = 1;
 const SOUTH: usize = 2;
 const WEST: usize = 3;
 
-impl GraphNode {
-	pub fn new(x: f64, y: f64,) -> GraphNode {
+impl<S: Scalar> GraphNode<S> {
+	pub fn new(x: S, y: S,) -> GraphNode<S> {
 		GraphNode{
 			neighbours: [None, None, None, None],
 			x: x, y: y, 
-			shortest_path: None, cost: std::f64::INFINITY,
+			shortest_path: None, cost: S::INFINITY,
+			region: regions::NO_REGION,
+			portal: None,
+			clearance: [(S::NEG_INFINITY, S::ZERO); 2],
 		}
 	}
 	
@@ -1248,11 +1483,11 @@ impl GraphNode {
 
 /// used for insert_obstacle
 #[derive(Copy, Clone, PartialEq)]
-struct MinSortableEdge((usize, usize), f64);
+struct MinSortableEdge<S>((usize, usize), S);
 
-impl Ord for MinSortableEdge {
+impl<S: Scalar> Ord for MinSortableEdge<S> {
 	/// Panics if one or both of the floats is NaN
-	fn cmp (&self, other: &MinSortableEdge) -> Ordering {
+	fn cmp (&self, other: &MinSortableEdge<S>) -> Ordering {
 		if (self.0).0 == (other.0).0 && (self.0).1 == (other.0).1 //Equality defined over edge points
 			{return Ordering::Equal;}		
 		if self.1.is_nan() || other.1.is_nan(){
@@ -1265,10 +1500,27 @@ impl Ord for MinSortableEdge {
 		}
 	}
 }
-impl PartialOrd for MinSortableEdge {
-    fn partial_cmp(&self, other: &MinSortableEdge) -> Option<Ordering> {
+impl<S: Scalar> PartialOrd for MinSortableEdge<S> {
+    fn partial_cmp(&self, other: &MinSortableEdge<S>) -> Option<Ordering> {
         Some(self.cmp(other)) //use comparison implemented for Ord
     }
 }
-impl Eq for MinSortableEdge {}
+impl<S: Scalar> Eq for MinSortableEdge<S> {}
+
+/// used for recompute_paths, the node with the lowest cost is the greatest
+#[derive(Copy, Clone, PartialEq)]
+struct MinSortableNode<S>(usize, S);
+
+impl<S: Scalar> Ord for MinSortableNode<S> {
+	/// Panics if one or both of the floats is NaN
+	fn cmp (&self, other: &MinSortableNode<S>) -> Ordering {
+		other.1.partial_cmp(&self.1).expect("Can't compare NaN here.")
+	}
+}
+impl<S: Scalar> PartialOrd for MinSortableNode<S> {
+	fn partial_cmp(&self, other: &MinSortableNode<S>) -> Option<Ordering> {
+		Some(self.cmp(other))
+	}
+}
+impl<S: Scalar> Eq for MinSortableNode<S> {}
 
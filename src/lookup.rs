@@ -0,0 +1,126 @@
+//! Index of the edges by their position, to find the edges next to a coordinate without going through the whole graph
+
+use super::{JkmShortestPathMap, Scalar, NORTH, EAST, SOUTH, WEST};
+
+// Number of cells along each side of the map
+const CELLS: usize = 32;
+
+/// Regular grid of cells over the map, each lists the nodes that lie in it or have their edge to the east or to the south pass through it.
+/// Nodes outside of the map count to the cell on the border that is closest to them.
+#[derive(Clone, Debug)]
+pub(crate) struct EdgeIndex<S> {
+	area: (S,S,S,S),
+	cells: Vec<Vec<usize>>,
+	// cells that list each node
+	listed: Vec<Vec<usize>>,
+}
+
+impl<S: Scalar> EdgeIndex<S> {
+	pub(crate) fn new(area: (S,S,S,S)) -> EdgeIndex<S> {
+		EdgeIndex { area, cells: vec![Vec::new(); CELLS * CELLS], listed: Vec::new() }
+	}
+
+	fn column(&self, x: S) -> usize {
+		cell(x, self.area.0, self.area.2)
+	}
+
+	fn row(&self, y: S) -> usize {
+		cell(y, self.area.1, self.area.3)
+	}
+}
+
+// Index of the cell along one side, clamped to the map
+fn cell<S: Scalar>(value: S, start: S, length: S) -> usize {
+	if length <= S::ZERO { return 0; }
+	let i = ((value - start).to_f64() / length.to_f64() * CELLS as f64).floor();
+	if i < 0.0 { 0 } else if i >= CELLS as f64 { CELLS - 1 } else { i as usize }
+}
+
+impl<S: Scalar> JkmShortestPathMap<S> {
+
+	// Lists the nodes again that have been changed, moved or erased, together with the nodes whose edges end on them
+	pub(crate) fn update_edge_index(&mut self, touched: &[usize]) {
+		let mut nodes = touched.to_vec();
+		let live = self.graph.len();
+		for &n in touched.iter().filter(|&&n| n < live) {
+			nodes.extend(self.graph[n].neighbours[NORTH]);
+			nodes.extend(self.graph[n].neighbours[WEST]);
+		}
+		nodes.sort_unstable();
+		nodes.dedup();
+		let index = &mut self.edges;
+		if index.listed.len() < self.graph.len() { index.listed.resize(self.graph.len(), Vec::new()); }
+		for &n in nodes.iter() {
+			if n >= index.listed.len() { continue; }
+			for c in std::mem::take(&mut index.listed[n]) {
+				index.cells[c].retain(|&m| m != n);
+			}
+			if n >= self.graph.len() || self.graph[n].x == S::NEG_INFINITY { continue; }
+			let node = &self.graph[n];
+			let (column, row) = (index.column(node.x), index.row(node.y));
+			let mut cells = vec![row * CELLS + column];
+			if let Some(right) = node.neighbours[EAST] {
+				cells.extend((column + 1..=index.column(self.graph[right].x)).map(|c| row * CELLS + c));
+			}
+			if let Some(bot) = node.neighbours[SOUTH] {
+				cells.extend((row + 1..=index.row(self.graph[bot].y)).map(|r| r * CELLS + column));
+			}
+			for &c in cells.iter() {
+				index.cells[c].push(n);
+			}
+			index.listed[n] = cells;
+		}
+		index.listed.truncate(self.graph.len());
+	}
+
+	// Lists all nodes in a new index over the current map
+	pub(crate) fn rebuild_edge_index(&mut self) {
+		self.edges = EdgeIndex::new(self.map);
+		let all: Vec<usize> = (0..self.graph.len()).collect();
+		self.update_edge_index(&all);
+	}
+
+	// Node that lies exactly on the coordinate
+	pub(crate) fn indexed_node(&self, x: S, y: S) -> Option<usize> {
+		let index = &self.edges;
+		index.cells[index.row(y) * CELLS + index.column(x)].iter().cloned()
+			.find(|&n| self.graph[n].x == x && self.graph[n].y == y)
+	}
+
+	// Closest edge that a straight line from the coordinate in the direction meets, together with the coordinate where it meets it.
+	// Only edges across the direction count, the edge is returned as its upper or left node. Edges through the coordinate itself count as well.
+	pub(crate) fn nearest_edge(&self, x: S, y: S, direction: usize) -> Option<(usize, S)> {
+		let index = &self.edges;
+		let vertical = direction == NORTH || direction == SOUTH;
+		let (mut c, mut r) = (index.column(x), index.row(y));
+		loop {
+			let mut nearest: Option<(usize, S)> = None;
+			for &n in index.cells[r * CELLS + c].iter() {
+				let node = &self.graph[n];
+				let hit = if vertical {
+					match node.neighbours[EAST] { Some(right) if node.x <= x && self.graph[right].x >= x => Some(node.y), _ => None }
+				}
+				else {
+					match node.neighbours[SOUTH] { Some(bot) if node.y <= y && self.graph[bot].y >= y => Some(node.x), _ => None }
+				};
+				let hit = hit.filter(|&at| match direction { NORTH => at <= y, SOUTH => at >= y, WEST => at <= x, _ => at >= x });
+				if let Some(at) = hit {
+					let closer = match nearest {
+						Some((_, best)) => if direction == NORTH || direction == WEST { at > best } else { at < best },
+						None => true,
+					};
+					if closer { nearest = Some((n, at)); }
+				}
+			}
+			// all edges in the following cells are further away
+			if nearest.is_some() { return nearest; }
+			match direction {
+				NORTH if r > 0 => r -= 1,
+				SOUTH if r + 1 < CELLS => r += 1,
+				WEST if c > 0 => c -= 1,
+				EAST if c + 1 < CELLS => c += 1,
+				_ => return None,
+			}
+		}
+	}
+}
@@ -0,0 +1,357 @@
+//! Temporary nodes and edges on top of the graph, for searches that must not change the map
+
+use std::collections::{BinaryHeap, HashMap};
+
+use super::{JkmShortestPathMap, Scalar, MinSortableNode, NORTH, EAST, SOUTH, WEST, PORTAL};
+use super::{obstacle_contains_coordinate, h_line_overlaps_obstacle, v_line_overlaps_obstacle};
+use touch::{Seams, TouchPolicy, horizontal, vertical};
+use zones;
+use clearance::corridor_width;
+
+// An obstacle that is not inserted, all edges through it count as cut
+struct Blocker<S> {
+	area: (S,S,S,S),
+	// where the blocker touches obstacles, empty with TouchPolicy::Open
+	seams: Seams<S>,
+}
+
+/// How a search gets from one node to the next
+#[derive(Copy, Clone, PartialEq)]
+pub(crate) enum Move {
+	/// Along the edge of the graph in this direction
+	Edge(usize),
+	/// Along an edge of the overlay
+	Link,
+	/// Through a portal
+	Jump,
+}
+
+/// Nodes and edges that are added to the graph without changing it, like splice_point() does on the map.
+/// The nodes of the overlay have the indices after the nodes of the graph.
+pub(crate) struct Overlay<S> {
+	base: usize,
+	points: Vec<(S, S)>,
+	links: HashMap<usize, Vec<usize>>,
+	blocker: Option<Blocker<S>>,
+}
+
+impl<S: Scalar> Overlay<S> {
+	pub(crate) fn new(map: &JkmShortestPathMap<S>) -> Overlay<S> {
+		Overlay { base: map.graph.len(), points: Vec::new(), links: HashMap::new(), blocker: None }
+	}
+
+	/// Treats the obstacle as if it was inserted: edges through it are cut, nodes around it are only added by surround().
+	pub(crate) fn blocking(map: &JkmShortestPathMap<S>, (x, y, w, h): (S,S,S,S)) -> Overlay<S> {
+		let mut overlay = Overlay::new(map);
+		let (mx, my, mw, mh) = map.map;
+		// ignored and cut at the border of the map like in insert_obstacle()
+		if x > mx + mw || y > my + mh { return overlay; }
+		let w = if x + w > mx + mw { mx + mw - x + S::from_f64(4.0) * S::EPS } else { w };
+		let h = if y + h > my + mh { my + mh - y + S::from_f64(4.0) * S::EPS } else { h };
+		let area = (x, y, w, h);
+		let mut seams = Seams::new();
+		if map.touch == TouchPolicy::Closed {
+			let touching: Vec<(S,S,S,S)> = map.obstacles.iter().cloned().filter(|o| o.0 <= x + w && o.0 + o.2 >= x && o.1 <= y + h && o.1 + o.3 >= y).collect();
+			seams.add(area, &touching);
+		}
+		overlay.blocker = Some(Blocker { area, seams });
+		overlay
+	}
+
+	/// Number of nodes in the graph and the overlay together
+	pub(crate) fn len(&self) -> usize {
+		self.base + self.points.len()
+	}
+
+	pub(crate) fn position(&self, map: &JkmShortestPathMap<S>, n: usize) -> (S, S) {
+		if n < self.base { (map.graph[n].x, map.graph[n].y) } else { self.points[n - self.base] }
+	}
+
+	/// All ways to leave the node, together with their cost
+	pub(crate) fn moves(&self, map: &JkmShortestPathMap<S>, n: usize) -> Vec<(usize, S, Move)> {
+		let p = self.position(map, n);
+		let mut moves = Vec::new();
+		if n < self.base {
+			for direction in 0..4 {
+				if let Some(m) = map.graph[n].neighbours[direction] {
+					let q = self.position(map, m);
+					if map.can_move(n, direction) && !self.cut(p, q) {
+						moves.push((m, distance(p, q), Move::Edge(direction)));
+					}
+				}
+			}
+			if let Some(end) = map.graph[n].portal.filter(|end| end.outgoing) {
+				if !self.inside(self.position(map, end.other)) {
+					moves.push((end.other, end.cost, Move::Jump));
+				}
+			}
+		}
+		if let Some(links) = self.links.get(&n) {
+			for &m in links.iter() {
+				let q = self.position(map, m);
+				if zones::move_is_allowed(&map.zones, p, q) {
+					moves.push((m, distance(p, q), Move::Link));
+				}
+			}
+		}
+		moves
+	}
+
+	/// Returns the node on the coordinate, if there is none it is added and linked in all four directions like splice_point() does.
+	/// The coordinate must not be blocked.
+	pub(crate) fn add_point(&mut self, map: &JkmShortestPathMap<S>, p: (S, S)) -> usize {
+		if let Some(n) = map.indexed_node(p.0, p.1) {
+			return n;
+		}
+		if let Some(i) = self.points.iter().position(|&q| q == p) {
+			return self.base + i;
+		}
+		let n = self.add_node(p);
+		for &direction in [NORTH, EAST, SOUTH, WEST].iter() {
+			self.cast(map, n, direction);
+		}
+		n
+	}
+
+	/// Clearance of a move returned by moves(), see JkmShortestPathMap::edge_clearance()
+	pub(crate) fn clearance(&self, map: &JkmShortestPathMap<S>, n: usize, m: usize, step: Move) -> S {
+		match step {
+			Move::Edge(direction) => map.edge_clearance(n, direction),
+			Move::Jump => S::INFINITY,
+			Move::Link => {
+				let (p, q) = (self.position(map, n), self.position(map, m));
+				if p.1 == q.1 { corridor_width(&map.obstacles, map.map, p.0.min(q.0), p.0.max(q.0), p.1, horizontal) }
+				else { corridor_width(&map.obstacles, map.map, p.1.min(q.1), p.1.max(q.1), p.0, vertical) }
+			}
+		}
+	}
+
+	/// Cost of the shortest path from the node to the destination once the blocker is inserted.
+	/// Only the nodes whose current path goes through the blocker are searched again.
+	pub(crate) fn cost_with_blocker(&mut self, map: &JkmShortestPathMap<S>, from: usize) -> S {
+		let mut kept = HashMap::new();
+		if self.keeps_path(map, from, &mut kept) { return map.graph[from].cost; }
+		self.surround(map);
+
+		// entries with this index hold the cost of a whole path, over a node that keeps its path
+		let done = usize::MAX;
+		let mut costs = HashMap::new();
+		let mut queue = BinaryHeap::new();
+		costs.insert(from, S::ZERO);
+		queue.push(MinSortableNode(from, S::ZERO));
+		while let Some(MinSortableNode(n, cost)) = queue.pop() {
+			if n == done { return cost; }
+			if costs.get(&n).is_some_and(|&c| cost > c) { continue; }
+			if n < self.base && self.keeps_path(map, n, &mut kept) {
+				if map.graph[n].cost < S::INFINITY { queue.push(MinSortableNode(done, cost + map.graph[n].cost)); }
+				continue;
+			}
+			for (m, step, _) in self.moves(map, n) {
+				let next = cost + step;
+				if next < costs.get(&m).cloned().unwrap_or(S::INFINITY) {
+					costs.insert(m, next);
+					queue.push(MinSortableNode(m, next));
+				}
+			}
+		}
+		S::INFINITY
+	}
+
+	// Whether the path from the node to the destination avoids the blocker, then the node keeps its cost
+	fn keeps_path(&self, map: &JkmShortestPathMap<S>, n: usize, kept: &mut HashMap<usize, bool>) -> bool {
+		let mut chain = Vec::new();
+		let mut current = n;
+		let result = loop {
+			if let Some(&known) = kept.get(&current) { break known; }
+			let p = self.position(map, current);
+			if self.inside(p) || chain.len() > map.graph.len() { break false; }
+			chain.push(current);
+			match map.next_node(current) {
+				Some(next) => {
+					if map.graph[current].shortest_path != Some(PORTAL) && self.cut(p, self.position(map, next)) { break false; }
+					current = next;
+				}
+				None => break true,
+			}
+		};
+		for c in chain {
+			kept.insert(c, result);
+		}
+		result
+	}
+
+	// Adds the nodes that insert_obstacle() would create around the blocker: its corners with their links away from it
+	// and the ends of all edges that are cut, all linked along its border
+	fn surround(&mut self, map: &JkmShortestPathMap<S>) {
+		let (x, y, w, h) = match self.blocker { Some(ref b) => b.area, None => return };
+		// nodes on the north, east, south and west side, with their coordinate along the side
+		let mut sides: [Vec<(S, usize)>; 4] = Default::default();
+		for n in 0..self.base {
+			let (nx, ny) = (map.graph[n].x, map.graph[n].y);
+			if nx >= x && nx <= x + w {
+				if ny == y { sides[NORTH].push((nx, n)); }
+				if ny == y + h { sides[SOUTH].push((nx, n)); }
+			}
+			if ny >= y && ny <= y + h {
+				if nx == x { sides[WEST].push((ny, n)); }
+				if nx == x + w { sides[EAST].push((ny, n)); }
+			}
+			if let Some(up) = map.graph[n].neighbours[NORTH] {
+				let top = map.graph[up].y;
+				if v_line_overlaps_obstacle((nx, top, ny), (x, y, w, h)) {
+					if top < y { self.add_end(map, (nx, y), nx, up, &mut sides[NORTH]); }
+					if ny > y + h { self.add_end(map, (nx, y + h), nx, n, &mut sides[SOUTH]); }
+				}
+			}
+			if let Some(right) = map.graph[n].neighbours[EAST] {
+				let end = map.graph[right].x;
+				if h_line_overlaps_obstacle((nx, ny, end), (x, y, w, h)) {
+					if nx < x { self.add_end(map, (x, ny), ny, n, &mut sides[WEST]); }
+					if end > x + w { self.add_end(map, (x + w, ny), ny, right, &mut sides[EAST]); }
+				}
+			}
+		}
+		let corners = [((x, y), [NORTH, WEST]), ((x + w, y), [NORTH, EAST]), ((x + w, y + h), [SOUTH, EAST]), ((x, y + h), [SOUTH, WEST])];
+		for &(corner, away) in corners.iter() {
+			if self.is_blocked(map, corner) { continue; }
+			let c = self.add_node(corner);
+			for &direction in away.iter() {
+				self.cast(map, c, direction);
+			}
+			sides[away[0]].push((corner.0, c));
+			sides[away[1]].push((corner.1, c));
+		}
+		for side in sides.iter_mut() {
+			side.sort_by(|a, b| a.0.partial_cmp(&b.0).expect("Can't compare NaN here."));
+			for pair in side.windows(2) {
+				let (p, q) = (self.position(map, pair[0].1), self.position(map, pair[1].1));
+				if p == q || self.free(map, p, q) { self.link(pair[0].1, pair[1].1); }
+			}
+		}
+	}
+
+	// Adds a node where a cut edge reaches the border of the blocker, linked to the end of the edge outside of it
+	fn add_end(&mut self, map: &JkmShortestPathMap<S>, p: (S, S), along: S, outside: usize, side: &mut Vec<(S, usize)>) {
+		if self.is_blocked(map, p) || self.cut(p, self.position(map, outside)) { return; }
+		let v = self.add_node(p);
+		self.link(v, outside);
+		side.push((along, v));
+	}
+
+	// Goes from the node in the direction until it is blocked, splitting all edges on the way and stopping at the first node,
+	// like link_to_north() and its siblings do
+	fn cast(&mut self, map: &JkmShortestPathMap<S>, from: usize, direction: usize) {
+		let origin = self.position(map, from);
+		// distance along the way, the node that lies there or the ends of the edge that is crossed
+		let mut hits: Vec<(S, usize, Option<usize>)> = Vec::new();
+		for n in 0..self.len() {
+			let p = self.position(map, n);
+			if let Some(d) = beyond(origin, p, direction) { hits.push((d, n, None)); }
+		}
+		for (a, b) in self.segments(map) {
+			let (p, q) = (self.position(map, a), self.position(map, b));
+			let crossing = if direction == NORTH || direction == SOUTH {
+				if p.1 == q.1 && p.0 < origin.0 && origin.0 < q.0 { Some((origin.0, p.1)) } else { None }
+			}
+			else if p.0 == q.0 && p.1 < origin.1 && origin.1 < q.1 { Some((p.0, origin.1)) } else { None };
+			if let Some(d) = crossing.and_then(|c| beyond(origin, c, direction)) { hits.push((d, a, Some(b))); }
+		}
+		// nodes before crossings at the same distance, and the shortest piece of an edge that has been split by the overlay before the longer ones
+		let length = |a: usize, b: Option<usize>| b.map_or(S::ZERO, |b| distance(self.position(map, a), self.position(map, b)));
+		hits.sort_by(|a, b| a.0.partial_cmp(&b.0).expect("Can't compare NaN here.")
+			.then(a.2.is_some().cmp(&b.2.is_some()))
+			.then(length(a.1, a.2).partial_cmp(&length(b.1, b.2)).expect("Can't compare NaN here.")));
+
+		let mut previous = from;
+		for (d, a, b) in hits {
+			let p = match direction { NORTH => (origin.0, origin.1 - d), SOUTH => (origin.0, origin.1 + d), EAST => (origin.0 + d, origin.1), _ => (origin.0 - d, origin.1) };
+			if !self.free(map, self.position(map, previous), p) { return; }
+			match b {
+				None => { self.link(previous, a); return; }
+				Some(b) => {
+					if self.is_blocked(map, p) { return; }
+					let v = self.add_node(p);
+					self.link(previous, v);
+					for &end in [a, b].iter() {
+						if !self.cut(p, self.position(map, end)) { self.link(v, end); }
+					}
+					previous = v;
+				}
+			}
+		}
+	}
+
+	// Each edge of the graph and each link of the overlay once, the upper or left end first
+	fn segments(&self, map: &JkmShortestPathMap<S>) -> Vec<(usize, usize)> {
+		let mut segments = Vec::new();
+		for n in 0..self.base {
+			for &direction in [EAST, SOUTH].iter() {
+				if let Some(m) = map.graph[n].neighbours[direction] { segments.push((n, m)); }
+			}
+		}
+		for (&n, links) in self.links.iter() {
+			let p = self.position(map, n);
+			for &m in links.iter() {
+				let q = self.position(map, m);
+				if (p.1 == q.1 && p.0 < q.0) || (p.0 == q.0 && p.1 < q.1) { segments.push((n, m)); }
+			}
+		}
+		segments
+	}
+
+	fn add_node(&mut self, p: (S, S)) -> usize {
+		self.points.push(p);
+		self.len() - 1
+	}
+
+	fn link(&mut self, a: usize, b: usize) {
+		self.links.entry(a).or_default().push(b);
+		self.links.entry(b).or_default().push(a);
+	}
+
+	// Whether the coordinate lies strictly inside of the blocker
+	fn inside(&self, (x, y): (S, S)) -> bool {
+		self.blocker.as_ref().is_some_and(|b| obstacle_contains_coordinate(b.area, x, y))
+	}
+
+	fn is_blocked(&self, map: &JkmShortestPathMap<S>, (x, y): (S, S)) -> bool {
+		map.coordinate_is_blocked(x, y) || self.inside((x, y))
+			|| self.blocker.as_ref().is_some_and(|b| b.seams.seal_coordinate(x, y))
+	}
+
+	// Whether the straight line between the coordinates goes through the blocker, or between it and an obstacle touching it
+	fn cut(&self, p: (S, S), q: (S, S)) -> bool {
+		let b = match self.blocker { Some(ref b) => b, None => return false };
+		if p == q { return false; }
+		if p.1 == q.1 {
+			let (x0, x1) = (p.0.min(q.0), p.0.max(q.0));
+			h_line_overlaps_obstacle((x0, p.1, x1), b.area) || b.seams.seal_h_line(x0, p.1, x1)
+		}
+		else {
+			let (y0, y1) = (p.1.min(q.1), p.1.max(q.1));
+			v_line_overlaps_obstacle((p.0, y0, y1), b.area) || b.seams.seal_v_line(p.0, y0, y1)
+		}
+	}
+
+	// Whether the straight line between the coordinates is free in the map and not cut by the blocker
+	fn free(&self, map: &JkmShortestPathMap<S>, p: (S, S), q: (S, S)) -> bool {
+		let open = if p.1 == q.1 { map.h_line_overlaps_no_obstacle(p.0.min(q.0), p.1, p.0.max(q.0)) }
+			else { map.v_line_overlaps_no_obstacle(p.0, p.1.min(q.1), p.1.max(q.1)) };
+		open && !self.cut(p, q)
+	}
+}
+
+fn distance<S: Scalar>(p: (S, S), q: (S, S)) -> S {
+	(p.0 - q.0).abs() + (p.1 - q.1).abs()
+}
+
+// Distance from the origin to the coordinate if it lies strictly ahead in the direction
+fn beyond<S: Scalar>(origin: (S, S), p: (S, S), direction: usize) -> Option<S> {
+	match direction {
+		NORTH if p.0 == origin.0 && p.1 < origin.1 => Some(origin.1 - p.1),
+		SOUTH if p.0 == origin.0 && p.1 > origin.1 => Some(p.1 - origin.1),
+		EAST if p.1 == origin.1 && p.0 > origin.0 => Some(p.0 - origin.0),
+		WEST if p.1 == origin.1 && p.0 < origin.0 => Some(origin.0 - p.0),
+		_ => None,
+	}
+}
@@ -0,0 +1,174 @@
+//! Portals that connect two arbitrary points of the map at a fixed cost, like teleport pads
+
+use super::{JkmShortestPathMap, Scalar, GraphNode, EAST, SOUTH, WEST};
+use query;
+
+/// Value of GraphNode::shortest_path if the shortest path continues through the portal of the node
+pub(crate) const PORTAL: usize = 4;
+
+/// The next step on the shortest path to the destination, see JkmShortestPathMap::next_step()
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum Step<S = f64> {
+	/// Walk straight to the coordinate
+	Walk(S, S),
+	/// Take the portal at the current coordinate, it ends at the given coordinate
+	Jump(S, S),
+}
+
+/// End of a portal that is stored in the node on which it lies
+#[derive(Copy, Clone, Debug)]
+pub(crate) struct PortalEnd<S> {
+	/// Node on the other end of the portal
+	pub(crate) other: usize,
+	pub(crate) cost: S,
+	/// Paths can jump from this node to the other one
+	pub(crate) outgoing: bool,
+	/// Paths can jump from the other node to this one
+	pub(crate) incoming: bool,
+}
+
+/// A portal as it has been added, the nodes on its ends are spliced in again when they have been erased
+#[derive(Copy, Clone, Debug)]
+pub(crate) struct Portal<S> {
+	pub(crate) from: (S, S),
+	pub(crate) to: (S, S),
+	cost: S,
+	bidirectional: bool,
+	// nodes on both ends while the portal is in the graph, kept up to date by swap_out_dead_nodes()
+	pub(crate) ends: Option<(usize, usize)>,
+}
+
+impl<S: Scalar> JkmShortestPathMap<S> {
+
+	/// Adds a portal that lets paths jump from the coordinate `from` to the coordinate `to` at the given cost.
+	/// If it is bidirectional, the jump is also possible in the other direction.
+	/// Both ends are spliced into the graph like the start and the destination. While one of the ends is blocked by an obstacle,
+	/// the portal can not be used.
+	///
+	/// #Panics
+	/// Panics if one of the ends is outside of the map or already the end of another portal, if both ends are identical
+	/// or if the cost is negative.
+	pub fn add_portal(&mut self, from: (S, S), to: (S, S), cost: S, bidirectional: bool) {
+		let (mx, my, mw, mh) = self.map;
+		for &(x, y) in [from, to].iter() {
+			if x < mx || x > mx + mw || y < my || y > my + mh {
+				panic!("The portal end [{}|{}] is outside of the map.", x, y);
+			}
+			if self.portals.iter().any(|p| p.from == (x, y) || p.to == (x, y)) {
+				panic!("There is already a portal at [{}|{}].", x, y);
+			}
+		}
+		if from == to { panic!("A portal can not end where it starts."); }
+		if cost.is_nan() || cost < S::ZERO { panic!("The cost of a portal has to be positive, got {}.", cost); }
+		self.portals.push(Portal { from, to, cost, bidirectional, ends: None });
+		self.change_log.record((from.0, from.1, S::ZERO, S::ZERO));
+		self.change_log.record((to.0, to.1, S::ZERO, S::ZERO));
+		self.mark_clusters((from.0, from.1, S::ZERO, S::ZERO));
+		self.mark_clusters((to.0, to.1, S::ZERO, S::ZERO));
+		self.after_update();
+	}
+
+	/// Returns the next step on the shortest path from the given coordinate to the destination.
+	/// Works like next_checkpoint() but tells apart walking and jumping through a portal.
+	pub fn next_step(&self, x: S, y: S) -> Option<Step<S>> {
+		query::next_step(self, x, y)
+	}
+
+	// Splices the ends of all portals into the graph that have lost their nodes, for example because an obstacle
+	// was inserted on top of them and removed again
+	pub(crate) fn restore_portals(&mut self) {
+		for p in 0..self.portals.len() {
+			let portal = self.portals[p];
+			if let Some((a, b)) = portal.ends {
+				// the index of an erased end can have been taken by another node
+				let attached = a < self.graph.len() && (self.graph[a].x, self.graph[a].y) == portal.from
+					&& self.graph[a].portal.map(|end| end.other) == Some(b);
+				if attached { continue; }
+				self.portals[p].ends = None;
+			}
+			if self.coordinate_is_blocked(portal.from.0, portal.from.1) || self.coordinate_is_blocked(portal.to.0, portal.to.1) {
+				continue;
+			}
+			let a = self.splice_point(portal.from);
+			let b = self.splice_point(portal.to);
+			self.graph[a].portal = Some(PortalEnd { other: b, cost: portal.cost, outgoing: true, incoming: portal.bidirectional });
+			self.graph[b].portal = Some(PortalEnd { other: a, cost: portal.cost, outgoing: portal.bidirectional, incoming: true });
+			self.portals[p].ends = Some((a, b));
+			for &n in [a, b].iter() {
+				self.update_node(n);
+				self.update_neighbours(n);
+			}
+		}
+	}
+
+	// Tells the portals that a node with a portal end has been moved to another index
+	pub(crate) fn move_portal_end(&mut self, from: usize, to: usize) {
+		for portal in self.portals.iter_mut() {
+			if let Some((ref mut a, ref mut b)) = portal.ends {
+				if *a == from { *a = to; }
+				if *b == from { *b = to; }
+			}
+		}
+	}
+
+	// Removes the portal of the node from both of its ends, all paths that jump through it are invalidated
+	pub(crate) fn detach_portal(&mut self, n: usize) {
+		if let Some(end) = self.graph[n].portal {
+			self.graph[n].portal = None;
+			self.graph[end.other].portal = None;
+			for &m in [n, end.other].iter() {
+				self.change_log.record((self.graph[m].x, self.graph[m].y, S::ZERO, S::ZERO));
+			}
+			if self.graph[n].shortest_path == Some(PORTAL) {
+				self.invalidate_paths_through_node(n);
+			}
+			if self.graph[end.other].shortest_path == Some(PORTAL) {
+				self.invalidate_paths_through_node(end.other);
+			}
+		}
+	}
+
+	// Index of the node exactly on the coordinate
+	fn exact_node(&self, (x, y): (S, S)) -> Option<usize> {
+		self.graph.iter().position(|node| node.x == x && node.y == y)
+	}
+
+	// Returns the node on the coordinate, if there is none it is created and linked in all four directions
+	fn splice_point(&mut self, (x, y): (S, S)) -> usize {
+		if let Some(n) = self.exact_node((x, y)) {
+			return n;
+		}
+		// split the edge if the coordinate lies on one
+		for n in 0..self.graph.len() {
+			if let Some(right) = self.graph[n].neighbours[EAST] {
+				if self.graph[n].y == y && self.graph[n].x < x && self.graph[right].x > x {
+					let new_node = self.split_edge(n, EAST, x);
+					self.link_to_north(new_node);
+					self.link_to_south(new_node);
+					self.update_node(new_node);
+					self.update_neighbours(new_node);
+					return new_node;
+				}
+			}
+			if let Some(bot) = self.graph[n].neighbours[SOUTH] {
+				if self.graph[n].x == x && self.graph[n].y < y && self.graph[bot].y > y {
+					let new_node = self.split_edge(n, SOUTH, y);
+					self.link_to_east(new_node);
+					self.link_to_west(new_node);
+					self.update_node(new_node);
+					self.update_neighbours(new_node);
+					return new_node;
+				}
+			}
+		}
+		let new_node = self.graph.len();
+		self.graph.push(Box::new(GraphNode::new(x, y)));
+		self.link_to_north(new_node);
+		if self.graph[new_node].neighbours[EAST].is_none() { self.link_to_east(new_node); }
+		if self.graph[new_node].neighbours[SOUTH].is_none() { self.link_to_south(new_node); }
+		if self.graph[new_node].neighbours[WEST].is_none() { self.link_to_west(new_node); }
+		self.update_node(new_node);
+		self.update_neighbours(new_node);
+		new_node
+	}
+}
@@ -0,0 +1,117 @@
+//! Checkpoint queries that work on any representation of the graph, the map itself as well as its snapshots
+
+use super::{Scalar, EAST, SOUTH};
+use portals::Step;
+
+/// Read access to a graph with the shortest paths to a single destination
+pub(crate) trait PathGraph<S> {
+	fn node_count(&self) -> usize;
+	fn position(&self, n: usize) -> (S, S);
+	fn cost(&self, n: usize) -> S;
+	fn neighbour(&self, n: usize, direction: usize) -> Option<usize>;
+	// next node on the shortest path to the destination
+	fn next_node(&self, n: usize) -> Option<usize>;
+	// whether the shortest path of the node continues through a portal
+	fn is_jump(&self, n: usize) -> bool;
+	fn destination(&self) -> usize;
+	// line: (x0, y, x1) where x0 < x1
+	fn h_line_is_free(&self, x0: S, y: S, x1: S) -> bool;
+	// line: (x, y0, y1) where y0 < y1
+	fn v_line_is_free(&self, x: S, y0: S, y1: S) -> bool;
+	// whether a path may go straight from one coordinate to the other, i.e. no directional zone forbids it
+	fn move_is_allowed(&self, from: (S, S), to: (S, S)) -> bool;
+}
+
+// See JkmShortestPathMap::nearest_checkpoint()
+pub(crate) fn nearest_checkpoint<S: Scalar, G: PathGraph<S>>(g: &G, x: S, y: S) -> Option<(S,S)> {
+	let destination = g.position(g.destination());
+	if destination.0 == x && destination.1 == y {
+		return Some((x,y));
+	}
+
+	let mut nearest = (None, S::INFINITY, S::INFINITY);
+
+	for n in 0..g.node_count() {
+		let (node_x, node_y) = g.position(n);
+		if let Some(right_index) = g.neighbour(n, EAST) {
+			let (right_x, _) = g.position(right_index);
+			if node_x <= x && right_x >= x {
+				let new_y = node_y;
+				// an end of the edge that can not be reached from the coordinate is treated as if it had no path
+				let node_cost = if g.move_is_allowed((x, new_y), (node_x, node_y)) { g.cost(n) } else { S::INFINITY };
+				let right_cost = if g.move_is_allowed((x, new_y), (right_x, node_y)) { g.cost(right_index) } else { S::INFINITY };
+				let total_cost;
+				let cost_to_edge = (y-new_y).abs();
+				let cost_on_edge = if (node_cost + x - node_x ) < (right_cost + right_x - x)
+									    {total_cost = node_cost + x - node_x + cost_to_edge; x - node_x}
+								   else {total_cost = right_cost + right_x - x + cost_to_edge; right_x - x};
+
+				if total_cost <= nearest.1 && cost_on_edge + cost_to_edge >= S::EPS
+					&& (total_cost < nearest.1
+						|| (cost_on_edge + cost_to_edge < nearest.2 && total_cost < S::INFINITY )
+					) && ((y < new_y && g.v_line_is_free(x, y, new_y ))
+						||( y > new_y && g.v_line_is_free(x, new_y, y )))
+					&& g.move_is_allowed((x, y), (x, new_y))
+				{
+					nearest = (Some((x,new_y)), total_cost, cost_on_edge + cost_to_edge);
+				}
+			}
+		}
+		if let Some(bot_index) = g.neighbour(n, SOUTH) {
+			let (_, bot_y) = g.position(bot_index);
+			if node_y <= y && bot_y >= y {
+				let new_x = node_x;
+				let node_cost = if g.move_is_allowed((new_x, y), (node_x, node_y)) { g.cost(n) } else { S::INFINITY };
+				let bot_cost = if g.move_is_allowed((new_x, y), (node_x, bot_y)) { g.cost(bot_index) } else { S::INFINITY };
+				let total_cost;
+				let cost_to_edge = (x-new_x).abs();
+				let cost_on_edge = if (node_cost + y - node_y ) < (bot_cost + bot_y - y)
+									    {total_cost = node_cost + y - node_y + cost_to_edge; y - node_y}
+								   else {total_cost = bot_cost + bot_y - y + cost_to_edge; bot_y - y};
+				if total_cost <= nearest.1  && cost_on_edge + cost_to_edge >= S::EPS
+					&& (total_cost < nearest.1
+						 || (cost_on_edge + cost_to_edge < nearest.2 && total_cost < S::INFINITY  )
+						) && ((x < new_x && g.h_line_is_free(x, y, new_x ))
+						|| (x > new_x && g.h_line_is_free(new_x, y, x )))
+					&& g.move_is_allowed((x, y), (new_x, y))
+				{
+					nearest = (Some((new_x,y)), total_cost, cost_on_edge + cost_to_edge);
+				}
+			}
+		}
+	}
+	nearest.0
+}
+
+// See JkmShortestPathMap::next_checkpoint()
+pub(crate) fn next_checkpoint<S: Scalar, G: PathGraph<S>>(g: &G, x: S, y: S) -> Option<(S,S)> {
+	let destination = g.position(g.destination());
+	if destination.0 == x && destination.1 == y {
+		return Some((x,y));
+	}
+
+	if let Some(n) = node_at(g, x, y) {
+		if let Some(next) = g.next_node(n) {
+			return Some(g.position(next));
+		}
+	}
+	nearest_checkpoint(g, x, y)
+}
+
+// See JkmShortestPathMap::next_step()
+pub(crate) fn next_step<S: Scalar, G: PathGraph<S>>(g: &G, x: S, y: S) -> Option<Step<S>> {
+	if let Some(n) = node_at(g, x, y) {
+		if g.is_jump(n) {
+			return g.next_node(n).map(|next| { let (x, y) = g.position(next); Step::Jump(x, y) });
+		}
+	}
+	next_checkpoint(g, x, y).map(|(x, y)| Step::Walk(x, y))
+}
+
+// Index of the node at the given coordinate, if there is one
+pub(crate) fn node_at<S: Scalar, G: PathGraph<S>>(g: &G, x: S, y: S) -> Option<usize> {
+	(0..g.node_count()).find(|&n| {
+		let (node_x, node_y) = g.position(n);
+		(node_x - x).abs() < S::EPS && (node_y - y).abs() < S::EPS
+	})
+}
@@ -0,0 +1,100 @@
+//! Connected regions of the graph, to tell apart coordinates that are cut off from the destination from those that are not on the map at all
+
+use std::collections::{HashSet, VecDeque};
+
+use super::{JkmShortestPathMap, Scalar, NORTH, EAST, SOUTH, WEST};
+
+/// Region of dead nodes
+pub(crate) const NO_REGION: usize = usize::MAX;
+/// Region that contains the destination
+pub const DESTINATION_REGION: usize = 0;
+
+impl<S: Scalar> JkmShortestPathMap<S> {
+
+	/// Returns the connected region of the map that contains the coordinate. All coordinates with the same region can reach each other,
+	/// except in directional zones: these are ignored for all regions besides DESTINATION_REGION.
+	/// The region of the destination is always DESTINATION_REGION, the ids of all other regions can change whenever the map changes.
+	/// Returns None if the coordinate is outside of the map or blocked by an obstacle.
+	pub fn region_id(&self, x: S, y: S) -> Option<usize> {
+		let (mx, my, mw, mh) = self.map;
+		if x < mx || x > mx + mw || y < my || y > my + mh || self.coordinate_is_blocked(x, y) {
+			return None;
+		}
+		self.visible_node(x, y).map(|n| self.graph[n].region)
+	}
+
+	/// Checks whether there is a path from the coordinate to the destination
+	pub fn is_reachable(&self, x: S, y: S) -> bool {
+		self.region_id(x, y) == Some(DESTINATION_REGION)
+	}
+
+	/// Returns the bounding box (x, y, w, h) of all nodes that have a path to the destination.
+	/// Targets outside of this rectangle are never reachable.
+	pub fn reachable_area(&self) -> (S, S, S, S) {
+		let mut min = (S::INFINITY, S::INFINITY);
+		let mut max = (S::NEG_INFINITY, S::NEG_INFINITY);
+		for node in self.graph.iter().filter(|node| node.region == DESTINATION_REGION) {
+			min = (min.0.min(node.x), min.1.min(node.y));
+			max = (max.0.max(node.x), max.1.max(node.y));
+		}
+		(min.0, min.1, max.0 - min.0, max.1 - min.1)
+	}
+
+	// Finds a node that can be reached from the coordinate by going straight to an edge and then along it.
+	// Only the closest edge in each direction has to be checked, all others are behind it.
+	fn visible_node(&self, x: S, y: S) -> Option<usize> {
+		if let Some(n) = self.indexed_node(x, y) {
+			return Some(n);
+		}
+		for &direction in [NORTH, SOUTH].iter() {
+			if let Some((n, edge_y)) = self.nearest_edge(x, y, direction) {
+				if self.v_line_overlaps_no_obstacle(x, y.min(edge_y), y.max(edge_y)) { return Some(n); }
+			}
+		}
+		for &direction in [WEST, EAST].iter() {
+			if let Some((n, edge_x)) = self.nearest_edge(x, y, direction) {
+				if self.h_line_overlaps_no_obstacle(x.min(edge_x), y, x.max(edge_x)) { return Some(n); }
+			}
+		}
+		None
+	}
+
+	// Assigns a region to the nodes that have changed since the last update. Nodes with a path to the destination are known from their cost,
+	// the regions that are cut off are only flooded again where one of their nodes changed, i.e. where they could have been split or joined.
+	pub(crate) fn label_regions(&mut self, touched: &[usize]) {
+		let mut cut_off = Vec::new();
+		let live = self.graph.len();
+		for &n in touched.iter().filter(|&&n| n < live) {
+			let label = if self.graph[n].x == S::NEG_INFINITY { NO_REGION }
+				else if self.graph[n].cost < S::INFINITY { DESTINATION_REGION }
+				else { cut_off.push(n); continue; };
+			// only touch nodes that changed, otherwise a checkpoint would have to store all of them
+			if self.graph[n].region != label {
+				self.graph[n].region = label;
+			}
+		}
+		let mut flooded = HashSet::new();
+		let mut used = HashSet::new();
+		let mut queue = VecDeque::new();
+		for seed in cut_off {
+			if flooded.contains(&seed) { continue; }
+			// the first region that is flooded keeps its label, the other parts of a region that was split get new ones
+			let old = self.graph[seed].region;
+			let label = if old != NO_REGION && old != DESTINATION_REGION && used.insert(old) { old }
+				else { self.next_region += 1; self.next_region - 1 };
+			flooded.insert(seed);
+			queue.push_back(seed);
+			while let Some(n) = queue.pop_front() {
+				if self.graph[n].region != label {
+					self.graph[n].region = label;
+				}
+				let portal = self.graph[n].portal.map(|end| end.other);
+				for neighbour in self.graph[n].neighbours.iter().chain(Some(&portal)).filter_map(|&neighbour| neighbour) {
+					if self.graph[neighbour].cost == S::INFINITY && flooded.insert(neighbour) {
+						queue.push_back(neighbour);
+					}
+				}
+			}
+		}
+	}
+}
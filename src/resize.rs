@@ -0,0 +1,100 @@
+//! Growing the map at runtime. The nodes on the old border are moved to the new one, which stretches all lines
+//! that ended on the border, so the rest of the graph stays as it is. Only the obstacles that reach the old border are cut again.
+
+use super::{JkmShortestPathMap, Scalar, GraphNode, NORTH, EAST, SOUTH, WEST};
+use layers::Layer;
+use border::BorderMode;
+
+impl<S: Scalar> JkmShortestPathMap<S> {
+
+	/// Extends the map to the new rectangle (x, y, w, h), which has to contain the old one.
+	/// The nodes on the old border are moved to the new border, only the edges along the border and around obstacles
+	/// that reach the old border or lie beyond it are cut again. The closed sides of the border are moved to the new border.
+	/// Only the shortest paths through the moved nodes are computed again.
+	/// Returns an error if the new rectangle does not contain the old map or if the map has several layers.
+	pub fn resize_map(&mut self, x: S, y: S, w: S, h: S) -> Result<(), String> {
+		let old = self.map;
+		let new = (x, y, w, h);
+		if x > old.0 || y > old.1 || x + w < old.0 + old.2 || y + h < old.1 + old.3 {
+			return Err(format!("The map can only grow, {:?} does not contain {:?}.", new, old));
+		}
+		if self.layers.len() > 1 { return Err("Maps with several layers can not be resized.".to_string()); }
+
+		let border = self.border;
+		self.set_border(BorderMode::Open, BorderMode::Open, BorderMode::Open, BorderMode::Open);
+		// the parts of these obstacles outside of the old map have not been cut into the graph
+		let touching: Vec<(S,S,S,S)> = self.obstacles.iter().cloned()
+			.filter(|&(ox, oy, ow, oh)| ox <= old.0 || oy <= old.1 || ox + ow >= old.0 + old.2 || oy + oh >= old.1 + old.3)
+			.collect();
+		// what is left of them outside of the map would end up inside of it
+		self.erase_nodes_outside_map();
+		self.cleanup();
+
+		self.move_border(old, new);
+		self.map = new;
+		self.update();
+		for &(ox, oy, ow, oh) in touching.iter() {
+			self.cut_obstacle(ox, oy, ow, oh);
+		}
+		self.swap_out_dead_nodes();
+		// the moved nodes keep the clearance of their old edges
+		self.changed_obstacles.push(new);
+		self.change_log.record(new);
+		self.layers[0] = Layer::new(new);
+		self.rebuild_edge_index();
+
+		self.set_border(border[0], border[1], border[2], border[3]);
+		self.after_update();
+		Ok(())
+	}
+
+	// Moves all nodes on the sides of the old border that grow to the new border.
+	// The start, the destination and portal ends keep their position, a new node takes their place on the border.
+	fn move_border(&mut self, old: (S,S,S,S), new: (S,S,S,S)) {
+		let moved = |(nx, ny): (S, S)| -> (S, S) {
+			let nx = if nx == old.0 { new.0 } else if nx == old.0 + old.2 { new.0 + new.2 } else { nx };
+			let ny = if ny == old.1 { new.1 } else if ny == old.1 + old.3 { new.1 + new.3 } else { ny };
+			(nx, ny)
+		};
+		let mut pinned = Vec::new();
+		for n in 0..self.graph.len() {
+			let position = (self.graph[n].x, self.graph[n].y);
+			if position.0 == S::NEG_INFINITY || moved(position) == position { continue; }
+			if n == self.start_point_index || n == self.end_point_index || self.graph[n].portal.is_some() {
+				pinned.push(n);
+			}
+			else {
+				// the edges of the node get longer, so every path through it has to be found again
+				self.invalidate_paths_through_node(n);
+				let (nx, ny) = moved(position);
+				self.graph[n].x = nx;
+				self.graph[n].y = ny;
+			}
+		}
+		for &p in pinned.iter() {
+			let position = (self.graph[p].x, self.graph[p].y);
+			let (qx, qy) = moved(position);
+			let q = self.graph.len();
+			self.graph.push(Box::new(GraphNode::new(qx, qy)));
+			// the neighbours along the border have been moved, they belong to the new node now
+			for direction in 0..4 {
+				if let Some(neighbour) = self.graph[p].neighbours[direction] {
+					let along = if direction == NORTH || direction == SOUTH { self.graph[neighbour].x == qx } else { self.graph[neighbour].y == qy };
+					if along {
+						if self.next_node(neighbour) == Some(p) { self.invalidate_paths_through_node(neighbour); }
+						self.graph[p].neighbours[direction] = None;
+						self.graph[q].neighbours[direction] = Some(neighbour);
+						self.graph[neighbour].neighbours[(direction + 2) % 4] = Some(q);
+					}
+				}
+			}
+		}
+		for &p in pinned.iter() {
+			if self.graph[p].neighbours[NORTH].is_none() { self.link_to_north(p); }
+			if self.graph[p].neighbours[EAST].is_none() { self.link_to_east(p); }
+			if self.graph[p].neighbours[SOUTH].is_none() { self.link_to_south(p); }
+			if self.graph[p].neighbours[WEST].is_none() { self.link_to_west(p); }
+			self.consider_node_as_update_root(p);
+		}
+	}
+}
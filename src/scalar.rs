@@ -0,0 +1,71 @@
+//! The number type of all coordinates and costs
+
+use std::fmt::{Debug, Display};
+use std::ops::{Add, Sub, Mul, Div, Neg, AddAssign, SubAssign};
+
+/// Number type of coordinates and costs, implemented for f64, f32, i32 and i64.
+/// Integers can be used as fixed-point numbers by scaling all coordinates with the same factor.
+/// The graph is built with additions, subtractions and comparisons only, with integers the results are bit-identical everywhere.
+pub trait Scalar: Copy + PartialOrd + Debug + Display + Send + Sync + 'static
+	+ Add<Output = Self> + Sub<Output = Self> + Mul<Output = Self> + Div<Output = Self> + Neg<Output = Self> + AddAssign + SubAssign
+{
+	const ZERO: Self;
+	/// Smallest distance the map works with. Obstacles that are cut at the border of the map reach a few EPS beyond it.
+	/// Coordinates closer than EPS are the same, for integers it is 1, so that all comparisons are exact.
+	const EPS: Self;
+	/// Cost of nodes without a path to the destination, everything at or above it counts as unreachable.
+	/// For integers it is far below the maximum value, so that adding distances to it does not overflow.
+	const INFINITY: Self;
+	/// Coordinate of erased nodes
+	const NEG_INFINITY: Self;
+
+	fn abs(self) -> Self;
+	fn min(self, other: Self) -> Self;
+	fn max(self, other: Self) -> Self;
+	fn is_nan(self) -> bool;
+	/// Converts constants and the results of geometric computations, integers round towards zero
+	fn from_f64(value: f64) -> Self;
+	fn to_f64(self) -> f64;
+}
+
+macro_rules! float_scalar {
+	($t: ident, $eps: expr) => {
+		impl Scalar for $t {
+			const ZERO: $t = 0.0;
+			const EPS: $t = $eps;
+			const INFINITY: $t = $t::INFINITY;
+			const NEG_INFINITY: $t = $t::NEG_INFINITY;
+
+			fn abs(self) -> $t { $t::abs(self) }
+			fn min(self, other: $t) -> $t { $t::min(self, other) }
+			fn max(self, other: $t) -> $t { $t::max(self, other) }
+			fn is_nan(self) -> bool { $t::is_nan(self) }
+			fn from_f64(value: f64) -> $t { value as $t }
+			fn to_f64(self) -> f64 { f64::from(self) }
+		}
+	}
+}
+
+macro_rules! integer_scalar {
+	($t: ident) => {
+		impl Scalar for $t {
+			const ZERO: $t = 0;
+			const EPS: $t = 1;
+			const INFINITY: $t = $t::MAX / 4;
+			const NEG_INFINITY: $t = $t::MIN / 4;
+
+			fn abs(self) -> $t { $t::abs(self) }
+			fn min(self, other: $t) -> $t { Ord::min(self, other) }
+			fn max(self, other: $t) -> $t { Ord::max(self, other) }
+			fn is_nan(self) -> bool { false }
+			fn from_f64(value: f64) -> $t { value as $t }
+			fn to_f64(self) -> f64 { self as f64 }
+		}
+	}
+}
+
+float_scalar!(f64, 1.0 / 1048576.0);
+// f32 has about 7 significant digits, a smaller EPS would vanish on maps that are a few thousand units wide
+float_scalar!(f32, 1.0 / 256.0);
+integer_scalar!(i32);
+integer_scalar!(i64);
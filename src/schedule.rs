@@ -0,0 +1,223 @@
+//! Obstacles that block the map only at certain times, like doors or drawbridges, and obstacles that are switched on and off by hand
+
+use std::mem;
+
+use super::{JkmShortestPathMap, Scalar};
+use validity::ChangeLog;
+
+/// Describes when a scheduled obstacle is blocking
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum Schedule {
+	/// Blocking from the time `from` until right before the time `until`
+	Window { from: f64, until: f64 },
+	/// Blocking for `active_for` time units in every `period`, the first period starts at `offset`
+	Periodic { period: f64, active_for: f64, offset: f64 },
+}
+
+impl Schedule {
+	/// Checks whether the obstacle is blocking at the given time
+	pub fn is_active(&self, t: f64) -> bool {
+		match *self {
+			Schedule::Window { from, until } => from <= t && t < until,
+			Schedule::Periodic { period, active_for, offset } => (t - offset).rem_euclid(period) < active_for,
+		}
+	}
+
+	/// Returns the first time after t at which the obstacle starts or stops blocking
+	pub fn next_change(&self, t: f64) -> Option<f64> {
+		let next = match *self {
+			Schedule::Window { from, until } => {
+				if t < from { from }
+				else if t < until { until }
+				else { return None; }
+			}
+			Schedule::Periodic { period, active_for, offset } => {
+				if active_for <= 0.0 || active_for >= period { return None; }
+				let phase = (t - offset).rem_euclid(period);
+				if phase < active_for { t + active_for - phase } else { t + period - phase }
+			}
+		};
+		if next > t { Some(next) } else { None }
+	}
+}
+
+/// Handle of an obstacle that is managed by the map, i.e. one that was added with add_scheduled_obstacle() or insert_toggleable_obstacle()
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub struct ObstacleId(usize);
+
+/// The current time of the map and all obstacles that can be switched on and off
+#[derive(Clone, Debug)]
+pub(crate) struct Timetable<S> {
+	time: f64,
+	pub(crate) obstacles: Vec<Option<ManagedObstacle<S>>>,
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct ManagedObstacle<S> {
+	pub(crate) obstacle: (S,S,S,S),
+	// None if the obstacle is only switched by set_obstacle_enabled()
+	schedule: Option<Schedule>,
+	active: bool,
+	// whether the obstacle has been inserted, i.e. the graph has nodes around it
+	pub(crate) placed: bool,
+	// sorted indices of the nodes inside of the obstacle or on its border, kept up to date by track_managed_nodes()
+	pub(crate) nodes: Vec<usize>,
+}
+
+impl<S> Timetable<S> {
+	pub(crate) fn new() -> Timetable<S> {
+		Timetable {
+			time: 0.0,
+			obstacles: Vec::new(),
+		}
+	}
+}
+
+impl<S: Scalar> JkmShortestPathMap<S> {
+
+	/// Adds an obstacle that only blocks the map when its schedule is active. It is inserted immediately if the schedule
+	/// is active at the current time of the map, later changes are applied by advance_time().
+	pub fn add_scheduled_obstacle(&mut self, x: S, y: S, w: S, h: S, schedule: Schedule) -> ObstacleId {
+		let active = schedule.is_active(self.timetable.time);
+		self.timetable.obstacles.push(Some(ManagedObstacle { obstacle: (x,y,w,h), schedule: Some(schedule), active, placed: active, nodes: Vec::new() }));
+		if active {
+			self.insert_obstacle(x,y,w,h);
+		}
+		ObstacleId(self.timetable.obstacles.len() - 1)
+	}
+
+	/// Adds an obstacle that can be switched on and off with set_obstacle_enabled().
+	/// Once the obstacle has been enabled, the nodes around it stay in the graph and switching it only
+	/// deletes or restores the edges that go through it, so the indices of existing nodes are not changed by toggling.
+	pub fn insert_toggleable_obstacle(&mut self, x: S, y: S, w: S, h: S, enabled: bool) -> ObstacleId {
+		self.timetable.obstacles.push(Some(ManagedObstacle { obstacle: (x,y,w,h), schedule: None, active: enabled, placed: enabled, nodes: Vec::new() }));
+		if enabled {
+			self.insert_obstacle(x,y,w,h);
+		}
+		ObstacleId(self.timetable.obstacles.len() - 1)
+	}
+
+	/// Switches an obstacle that is managed by the map on or off. This also works on scheduled obstacles,
+	/// however their schedule takes over again at the next change in advance_time().
+	/// Returns false if there is no such obstacle.
+	pub fn set_obstacle_enabled(&mut self, id: ObstacleId, enabled: bool) -> bool {
+		match self.timetable.obstacles.get_mut(id.0).and_then(|entry| entry.as_mut()) {
+			Some(entry) => {
+				if entry.active == enabled { return true; }
+				entry.active = enabled;
+			}
+			None => return false,
+		}
+		if enabled { self.enable_obstacle(id.0); } else { self.disable_obstacle(id.0); }
+		self.after_update();
+		true
+	}
+
+	/// Returns whether an obstacle that is managed by the map is currently blocking, None if there is no such obstacle
+	pub fn obstacle_is_enabled(&self, id: ObstacleId) -> Option<bool> {
+		self.timetable.obstacles.get(id.0).and_then(|entry| entry.as_ref()).map(|entry| entry.active)
+	}
+
+	/// Removes an obstacle that is managed by the map, independently of whether it is currently enabled.
+	/// Returns false if there is no such obstacle.
+	pub fn remove_scheduled_obstacle(&mut self, id: ObstacleId) -> bool {
+		let active = match self.timetable.obstacles.get(id.0).and_then(|entry| entry.as_ref()) {
+			Some(entry) => entry.active,
+			None => return false,
+		};
+		// remove_obstacle() would leave the nodes kept inside of the obstacle without edges, disabling it links them again
+		if active {
+			self.disable_obstacle(id.0);
+		}
+		self.timetable.obstacles[id.0] = None;
+		if active {
+			self.after_update();
+		}
+		true
+	}
+
+	/// The time that was last set with advance_time(), initially 0
+	pub fn time(&self) -> f64 {
+		self.timetable.time
+	}
+
+	/// Sets the current time and applies all changes of scheduled obstacles at once.
+	/// All obstacles that stop blocking are disabled first, afterwards the obstacles that start blocking are enabled.
+	/// Like toggleable obstacles, scheduled obstacles keep their nodes in the graph.
+	/// The regions, portals and other derived data are updated once after all changes.
+	pub fn advance_time(&mut self, t: f64) {
+		self.timetable.time = t;
+		let mut freed = Vec::new();
+		let mut blocked = Vec::new();
+		for (i, entry) in self.timetable.obstacles.iter_mut().enumerate() {
+			if let Some(ref mut entry) = *entry {
+				if let Some(schedule) = entry.schedule {
+					let active = schedule.is_active(t);
+					if active != entry.active {
+						entry.active = active;
+						if active { blocked.push(i); } else { freed.push(i); }
+					}
+				}
+			}
+		}
+		for &i in freed.iter() {
+			self.disable_obstacle(i);
+		}
+		for &i in blocked.iter() {
+			self.enable_obstacle(i);
+		}
+		if !freed.is_empty() || !blocked.is_empty() {
+			self.after_update();
+		}
+	}
+
+	/// Returns the next time after the current time at which a scheduled obstacle starts or stops blocking
+	pub fn next_scheduled_change(&self) -> Option<f64> {
+		self.next_change_after(self.timetable.time)
+	}
+
+	/// Returns the earliest time, starting at the current time, at which there is a path from the coordinate to the destination.
+	/// Only scheduled obstacles are expected to change. All schedules are followed for at most one period,
+	/// or until the end of their window, if no path opens in that time, None is returned.
+	/// The changes are applied to the map itself under a checkpoint and rolled back afterwards, so each step costs as much as
+	/// advance_time() and the rollback as much as undoing these changes. Path tokens, recorded path changes and stats are not affected.
+	pub fn next_opening(&mut self, x: S, y: S) -> Option<f64> {
+		if self.is_reachable(x, y) {
+			return Some(self.timetable.time);
+		}
+		let horizon = self.timetable.obstacles.iter().filter_map(|entry| entry.as_ref().and_then(|entry| entry.schedule))
+			.map(|schedule| match schedule {
+				Schedule::Window { until, .. } => until,
+				Schedule::Periodic { period, .. } => self.timetable.time + period,
+			})
+			.fold(self.timetable.time, f64::max);
+		// what is reported to the caller is set aside, the checkpoint restores everything else
+		let change_log = mem::replace(&mut self.change_log, ChangeLog::new());
+		let tracked_paths = self.tracked_paths.take();
+		let counters = mem::take(&mut self.counters);
+		let token = self.checkpoint();
+		let mut opening = None;
+		let mut t = self.timetable.time;
+		while let Some(next) = self.next_change_after(t) {
+			if next > horizon { break; }
+			self.advance_time(next);
+			if self.is_reachable(x, y) {
+				opening = Some(next);
+				break;
+			}
+			t = next;
+		}
+		self.rollback(token);
+		self.commit(token);
+		self.change_log = change_log;
+		self.tracked_paths = tracked_paths;
+		self.counters = counters;
+		opening
+	}
+
+	fn next_change_after(&self, t: f64) -> Option<f64> {
+		self.timetable.obstacles.iter().filter_map(|entry| entry.as_ref().and_then(|entry| entry.schedule))
+			.filter_map(|schedule| schedule.next_change(t))
+			.fold(None, |min, next| match min { Some(m) if m <= next => Some(m), _ => Some(next) })
+	}
+}
@@ -0,0 +1,64 @@
+//! Double buffering of a map, for queries from several threads while obstacles are inserted and removed
+
+use std::mem;
+use std::sync::{Mutex, RwLock, RwLockReadGuard};
+
+use super::{JkmShortestPathMap, Scalar};
+
+// A change that has been applied to the back buffer, it is applied again to the other buffer after they have been swapped
+type Change<S> = Box<dyn FnMut(&mut JkmShortestPathMap<S>) + Send>;
+
+/// Wraps a JkmShortestPathMap so that it can be queried from many threads while another thread updates it.
+/// There are two copies of the map: readers see the front one, which stays consistent while writers change the back one.
+/// Publishing swaps the two copies and applies the published changes once more to the new back copy, nothing is cloned after new().
+/// Therefore, the changes passed to write() run twice and must do the same on both copies, i.e. only depend on the map and on captured values.
+///
+/// #Panics
+/// All functions panic if another thread panicked while holding one of the internal locks.
+pub struct SharedPathMap<S = f64> {
+	front: RwLock<JkmShortestPathMap<S>>,
+	back: Mutex<(JkmShortestPathMap<S>, Vec<Change<S>>)>,
+}
+
+impl<S: Scalar> SharedPathMap<S> {
+	/// Publishes the given map as the first version
+	pub fn new(map: JkmShortestPathMap<S>) -> SharedPathMap<S> {
+		SharedPathMap {
+			front: RwLock::new(map.clone()),
+			back: Mutex::new((map, Vec::new())),
+		}
+	}
+
+	/// Returns the last published version of the map.
+	/// publish() waits until all returned guards have been dropped, so they should not be kept for long.
+	pub fn read(&self) -> RwLockReadGuard<'_, JkmShortestPathMap<S>> {
+		self.front.read().expect("Lock of the published map is poisoned.")
+	}
+
+	/// Applies a change to the map without publishing it. Readers keep seeing the previous version until publish() is called.
+	pub fn write<F, R>(&self, mut f: F) -> R where F: FnMut(&mut JkmShortestPathMap<S>) -> R + Send + 'static {
+		let mut back = self.back.lock().expect("Lock of the map is poisoned.");
+		let result = f(&mut back.0);
+		back.1.push(Box::new(move |map| { f(map); }));
+		result
+	}
+
+	/// Makes all changes applied with write() visible to the readers
+	pub fn publish(&self) {
+		let mut back = self.back.lock().expect("Lock of the map is poisoned.");
+		let back = &mut *back;
+		if back.1.is_empty() { return; }
+		mem::swap(&mut *self.front.write().expect("Lock of the published map is poisoned."), &mut back.0);
+		// the previous front is the back buffer now, it still misses the changes that have just been published
+		for mut change in back.1.drain(..) {
+			change(&mut back.0);
+		}
+	}
+
+	/// Applies a change to the map and publishes the result immediately
+	pub fn update<F, R>(&self, f: F) -> R where F: FnMut(&mut JkmShortestPathMap<S>) -> R + Send + 'static {
+		let result = self.write(f);
+		self.publish();
+		result
+	}
+}
@@ -0,0 +1,143 @@
+//! Read-only copies of the shortest paths that can be shared with other threads while the map keeps changing
+
+use std::sync::Arc;
+
+use super::{JkmShortestPathMap, Scalar, h_line_overlaps_obstacle, v_line_overlaps_obstacle};
+use query::{self, PathGraph};
+use portals::Step;
+use zones::{self, DirectionalZone};
+use touch::{Seams, TouchPolicy};
+
+const NONE: u32 = u32::MAX;
+
+/// Compact, immutable copy of the nodes and shortest paths of a JkmShortestPathMap, created by JkmShortestPathMap::snapshot().
+/// Later changes on the map do not affect the snapshot. Cloning a snapshot is cheap, all clones share the same data.
+#[derive(Clone, Debug)]
+pub struct PathSnapshot<S = f64> {
+	data: Arc<SnapshotData<S>>,
+}
+
+#[derive(Debug)]
+struct SnapshotData<S> {
+	nodes: Vec<SnapshotNode<S>>,
+	obstacles: Vec<(S,S,S,S)>,
+	zones: Vec<DirectionalZone<S>>,
+	// empty with TouchPolicy::Open
+	seams: Seams<S>,
+	destination: usize,
+}
+
+#[derive(Copy, Clone, Debug)]
+struct SnapshotNode<S> {
+	x: S, y: S, cost: S,
+	neighbours: [u32;4],
+	next: u32,
+	jump: bool,
+}
+
+fn to_option(i: u32) -> Option<usize> {
+	if i == NONE { None } else { Some(i as usize) }
+}
+
+impl<S: Scalar> JkmShortestPathMap<S> {
+	/// Freezes the current graph and shortest paths into a PathSnapshot.
+	/// Nodes that have been erased from the graph are left out, therefore node indices of the snapshot can differ from the map.
+	pub fn snapshot(&self) -> PathSnapshot<S> {
+		let mut new_index = vec![NONE; self.graph.len()];
+		let mut count = 0;
+		for (i, node) in self.graph.iter().enumerate() {
+			if node.x != S::NEG_INFINITY {
+				new_index[i] = count;
+				count += 1;
+			}
+		}
+		let translate = |i: Option<usize>| if let Some(i) = i { new_index[i] } else { NONE };
+		let nodes = self.graph.iter().enumerate()
+			.filter(|&(i, _)| new_index[i] != NONE)
+			.map(|(i, node)| SnapshotNode {
+				x: node.x, y: node.y, cost: node.cost,
+				neighbours: [translate(node.neighbours[0]), translate(node.neighbours[1]), translate(node.neighbours[2]), translate(node.neighbours[3])],
+				next: translate(self.next_node(i)),
+				jump: PathGraph::is_jump(self, i),
+			})
+			.collect();
+		PathSnapshot {
+			data: Arc::new(SnapshotData {
+				nodes,
+				obstacles: self.obstacles.clone(),
+				zones: self.zones.clone(),
+				seams: if self.touch == TouchPolicy::Closed { self.seams.clone() } else { Seams::new() },
+				destination: new_index[self.end_point_index] as usize,
+			})
+		}
+	}
+}
+
+impl<S: Scalar> PathSnapshot<S> {
+	/// Number of nodes in the graph
+	pub fn node_count(&self) -> usize {
+		self.data.nodes.len()
+	}
+
+	/// Coordinates of the node with the given index
+	pub fn node_position(&self, n: usize) -> (S, S) {
+		(self.data.nodes[n].x, self.data.nodes[n].y)
+	}
+
+	/// Neighbours of a node in the order north, east, south, west
+	pub fn node_neighbours(&self, n: usize) -> [Option<usize>;4] {
+		let neighbours = self.data.nodes[n].neighbours;
+		[to_option(neighbours[0]), to_option(neighbours[1]), to_option(neighbours[2]), to_option(neighbours[3])]
+	}
+
+	/// Cost of the shortest path from the node to the destination, infinity if there is no path
+	pub fn node_cost(&self, n: usize) -> S {
+		self.data.nodes[n].cost
+	}
+
+	/// Next node on the shortest path from the given node to the destination
+	pub fn next_node(&self, n: usize) -> Option<usize> {
+		to_option(self.data.nodes[n].next)
+	}
+
+	/// Coordinates of the destination
+	pub fn destination(&self) -> (S, S) {
+		self.node_position(self.data.destination)
+	}
+
+	/// Same as JkmShortestPathMap::nearest_checkpoint() at the time the snapshot was taken
+	pub fn nearest_checkpoint(&self, x: S, y: S) -> Option<(S,S)> {
+		query::nearest_checkpoint(self, x, y)
+	}
+
+	/// Same as JkmShortestPathMap::next_checkpoint() at the time the snapshot was taken
+	pub fn next_checkpoint(&self, x: S, y: S) -> Option<(S,S)> {
+		query::next_checkpoint(self, x, y)
+	}
+
+	/// Same as JkmShortestPathMap::next_step() at the time the snapshot was taken
+	pub fn next_step(&self, x: S, y: S) -> Option<Step<S>> {
+		query::next_step(self, x, y)
+	}
+}
+
+impl<S: Scalar> PathGraph<S> for PathSnapshot<S> {
+	fn node_count(&self) -> usize { self.data.nodes.len() }
+	fn position(&self, n: usize) -> (S, S) { (self.data.nodes[n].x, self.data.nodes[n].y) }
+	fn cost(&self, n: usize) -> S { self.data.nodes[n].cost }
+	fn neighbour(&self, n: usize, direction: usize) -> Option<usize> { to_option(self.data.nodes[n].neighbours[direction]) }
+	fn next_node(&self, n: usize) -> Option<usize> { to_option(self.data.nodes[n].next) }
+	fn is_jump(&self, n: usize) -> bool { self.data.nodes[n].jump }
+	fn destination(&self) -> usize { self.data.destination }
+	fn h_line_is_free(&self, x0: S, y: S, x1: S) -> bool {
+		!self.data.obstacles.iter().any(|&o| h_line_overlaps_obstacle((x0, y, x1), o))
+			&& !self.data.seams.seal_h_line(x0, y, x1)
+	}
+	fn v_line_is_free(&self, x: S, y0: S, y1: S) -> bool {
+		!self.data.obstacles.iter().any(|&o| v_line_overlaps_obstacle((x, y0, y1), o))
+			&& !self.data.seams.seal_v_line(x, y0, y1)
+	}
+	fn move_is_allowed(&self, from: (S, S), to: (S, S)) -> bool {
+		zones::move_is_allowed(&self.data.zones, from, to)
+	}
+}
@@ -0,0 +1,148 @@
+//! Counters that explain how much work the last change of the map has done
+
+use std::time::{Duration, Instant};
+
+use super::{JkmShortestPathMap, Scalar, NORTH, EAST};
+
+/// Size of the graph together with the work done by the last change, see JkmShortestPathMap::stats()
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Stats {
+	/// Length of the node vector, including erased nodes that have not been swapped out yet
+	pub nodes: usize,
+	/// Nodes that are part of the graph
+	pub live_nodes: usize,
+	/// Erased nodes that still take up a place in the node vector
+	pub dead_nodes: usize,
+	/// Connections between two nodes, each counted once although it is stored in both of them
+	pub edges: usize,
+	/// Obstacles that currently block the map, including enabled managed obstacles and the blockers of a closed border
+	pub obstacles: usize,
+	pub last_update: UpdateStats,
+}
+
+/// Work done by the last change of the map
+#[derive(Clone, Copy, PartialEq, Debug, Default)]
+pub struct UpdateStats {
+	/// Nodes whose shortest path was invalidated
+	pub invalidated: usize,
+	/// Nodes that were given a shorter path while the paths were propagated
+	pub relaxed: usize,
+	/// Deepest recursion reached in update_neighbours()
+	pub max_update_depth: usize,
+	/// Deepest recursion reached in invalidate_paths_through_node()
+	pub max_invalidation_depth: usize,
+	/// Time spent on portals, zones, regions and clearance after the graph was changed
+	pub bookkeeping: Duration,
+	/// Only set if the change was insert_obstacle()
+	pub insert_phases: InsertPhases,
+}
+
+/// Time spent in each phase of insert_obstacle()
+#[derive(Clone, Copy, PartialEq, Debug, Default)]
+pub struct InsertPhases {
+	/// Finding and deleting the edges through the obstacle and invalidating the paths over them
+	pub cut: Duration,
+	/// Creating the nodes around the obstacle and linking them to the graph
+	pub build: Duration,
+	/// Computing the shortest paths that have been invalidated
+	pub repair: Duration,
+	/// Sealing touching obstacles and swapping out erased nodes
+	pub shrink: Duration,
+}
+
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Counters {
+	// nothing is counted or timed unless the stats are enabled
+	enabled: bool,
+	pub(crate) current: UpdateStats,
+	last: UpdateStats,
+	update_depth: usize,
+	invalidation_depth: usize,
+}
+
+impl Counters {
+	// The time a change starts at, None if the stats are disabled
+	pub(crate) fn start(&self) -> Option<Instant> {
+		if self.enabled { Some(Instant::now()) } else { None }
+	}
+
+	pub(crate) fn enter_update(&mut self) {
+		if !self.enabled { return; }
+		self.update_depth += 1;
+		self.current.max_update_depth = self.current.max_update_depth.max(self.update_depth);
+	}
+
+	pub(crate) fn leave_update(&mut self) {
+		if !self.enabled { return; }
+		self.update_depth -= 1;
+	}
+
+	pub(crate) fn relax(&mut self) {
+		if !self.enabled { return; }
+		self.current.relaxed += 1;
+	}
+
+	pub(crate) fn enter_invalidation(&mut self) {
+		if !self.enabled { return; }
+		self.invalidation_depth += 1;
+		self.current.invalidated += 1;
+		self.current.max_invalidation_depth = self.current.max_invalidation_depth.max(self.invalidation_depth);
+	}
+
+	pub(crate) fn leave_invalidation(&mut self) {
+		if !self.enabled { return; }
+		self.invalidation_depth -= 1;
+	}
+
+	// The counters of the change that has just been finished are reported from now on
+	pub(crate) fn finish_update(&mut self, started: Option<Instant>) {
+		if !self.enabled { return; }
+		self.current.bookkeeping = started.map_or(Duration::default(), |started| started.elapsed());
+		self.last = self.current;
+		self.current = UpdateStats::default();
+	}
+}
+
+// Time since the last lap, the next lap starts now. Zero if the stats are disabled.
+pub(crate) fn lap(since: &mut Option<Instant>) -> Duration {
+	match *since {
+		Some(ref mut since) => {
+			let now = Instant::now();
+			let elapsed = now - *since;
+			*since = now;
+			elapsed
+		}
+		None => Duration::default(),
+	}
+}
+
+impl<S: Scalar> JkmShortestPathMap<S> {
+
+	/// Starts counting and timing the work done by each change of the map, which stats() reports.
+	/// Timing needs std::time::Instant, which is not available on every platform, e.g. wasm32-unknown-unknown.
+	pub fn enable_stats(&mut self) {
+		self.counters.enabled = true;
+	}
+
+	/// Stops counting and timing the work done by each change, stats() reports no work for the last change
+	pub fn disable_stats(&mut self) {
+		self.counters = Counters::default();
+	}
+
+	/// Counts the nodes, edges and obstacles and reports the work done by the last change of the map.
+	/// Counting takes time linear in the size of the graph, the work done by the last change is only collected after enable_stats().
+	pub fn stats(&self) -> Stats {
+		let dead_nodes = self.graph.iter().filter(|node| node.x == S::NEG_INFINITY).count();
+		let edges = self.graph.iter()
+			.map(|node| node.neighbours[NORTH].iter().count() + node.neighbours[EAST].iter().count())
+			.sum();
+		Stats {
+			nodes: self.graph.len(),
+			live_nodes: self.graph.len() - dead_nodes,
+			dead_nodes,
+			edges,
+			obstacles: self.obstacles.len(),
+			last_update: self.counters.last,
+		}
+	}
+}
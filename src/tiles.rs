@@ -0,0 +1,75 @@
+//! Conversion of tile maps into rectangular obstacles
+
+use super::{JkmShortestPathMap, Scalar};
+
+/// Merges the blocked tiles of a grid into rectangles (x, y, w, h), measured in tiles.
+/// The grid is stored row by row, `cells[y * width + x]` is true if the tile at (x, y) is blocked.
+/// Each blocked tile is covered by exactly one rectangle. Rows are merged first, then extended downwards as far as possible.
+/// The merge is greedy, it does not always find the smallest number of rectangles.
+/// Neighbouring rectangles only touch, therefore they do not block the path between them when they are inserted as they are.
+///
+/// #Panics
+/// Panics if the width is 0 or the number of cells is not a multiple of it.
+pub fn tile_rectangles(cells: &[bool], width: usize) -> Vec<(usize, usize, usize, usize)> {
+	let height = cells.len().checked_div(width).unwrap_or(0);
+	if width == 0 || height * width != cells.len() {
+		panic!("A grid of {} cells can not have rows of width {}.", cells.len(), width);
+	}
+	let mut covered = vec![false; cells.len()];
+	let free = |covered: &[bool], x: usize, y: usize| cells[y * width + x] && !covered[y * width + x];
+	let mut rectangles = Vec::new();
+	for y in 0..height {
+		for x in 0..width {
+			if !free(&covered, x, y) { continue; }
+			let mut w = 1;
+			while x + w < width && free(&covered, x + w, y) { w += 1; }
+			let mut h = 1;
+			while y + h < height && (x..x + w).all(|tx| free(&covered, tx, y + h)) { h += 1; }
+			for ty in y..y + h {
+				for tx in x..x + w {
+					covered[ty * width + tx] = true;
+				}
+			}
+			rectangles.push((x, y, w, h));
+		}
+	}
+	rectangles
+}
+
+impl<S: Scalar> JkmShortestPathMap<S> {
+
+	/// Creates a map of square cells from an occupancy grid, stored like in tile_rectangles(). The map starts at (0, 0)
+	/// and everything outside of the grid is blocked. The blocked cells are merged into rectangles that are extended by
+	/// a small overlap wherever they touch another blocked cell, also diagonally, so that no path can go between them.
+	/// The overlap is 1/64 of the cell size, at least EPS, free cells next to blocked ones become narrower by that much.
+	/// Beyond the border of the map, the rectangles only reach as far as insert_obstacle() cuts them, which is enough to overlap the border.
+	///
+	/// #Panics
+	/// Panics if the cell size is not positive, for all reasons tile_rectangles() panics or for all reasons new() panics.
+	pub fn from_occupancy_grid(cells: &[bool], width: usize, cell_size: S, start: (S, S), end: (S, S)) -> JkmShortestPathMap<S> {
+		if cell_size.is_nan() || cell_size <= S::ZERO { panic!("The cell size has to be positive, got {}.", cell_size); }
+		let rectangles = tile_rectangles(cells, width);
+		let height = cells.len() / width;
+		let to_map = |n: usize| S::from_f64(n as f64) * cell_size;
+		let mut spm = JkmShortestPathMap::new(start, end, (S::ZERO, S::ZERO, to_map(width), to_map(height)));
+		spm.add_map_border();
+
+		let overlap = S::from_f64(cell_size.to_f64() / 64.0).max(S::EPS);
+		// the same margin that insert_obstacle() leaves beyond the east and the south border
+		let margin = S::from_f64(4.0) * S::EPS;
+		let blocked = |x: isize, y: isize| x >= 0 && y >= 0 && (x as usize) < width && (y as usize) < height && cells[y as usize * width + x as usize];
+		for &(x, y, w, h) in rectangles.iter() {
+			let (x0, y0, x1, y1) = (x as isize, y as isize, (x + w) as isize, (y + h) as isize);
+			// the ranges include the diagonal neighbours of the corners
+			let west = x == 0 || (y0 - 1..y1 + 1).any(|ty| blocked(x0 - 1, ty));
+			let east = x + w == width || (y0 - 1..y1 + 1).any(|ty| blocked(x1, ty));
+			let north = y == 0 || (x0 - 1..x1 + 1).any(|tx| blocked(tx, y0 - 1));
+			let south = y + h == height || (x0 - 1..x1 + 1).any(|tx| blocked(tx, y1));
+			let extend = |side: bool| if side { overlap } else { S::ZERO };
+			let (left, top) = ((to_map(x) - extend(west)).max(-margin), (to_map(y) - extend(north)).max(-margin));
+			let (right, bottom) = ((to_map(x + w) + extend(east)).min(to_map(width) + margin), (to_map(y + h) + extend(south)).min(to_map(height) + margin));
+			spm.insert_obstacle(left, top, right - left, bottom - top);
+		}
+		spm
+	}
+}
@@ -0,0 +1,268 @@
+//! Switching obstacles on and off while the nodes around them stay in the graph
+
+use std::cmp::Ordering;
+
+use super::{JkmShortestPathMap, Scalar, GraphNode, NORTH, EAST, SOUTH, WEST, h_line_overlaps_obstacle, v_line_overlaps_obstacle};
+
+impl<S: Scalar> JkmShortestPathMap<S> {
+
+	// Blocks the area of the managed obstacle with index i in the timetable by cutting the edges of its nodes that go through it.
+	// If the obstacle has not been inserted before or the nodes around it are missing, it is inserted like any other obstacle instead.
+	// after_update() has to be called afterwards.
+	pub(crate) fn enable_obstacle(&mut self, i: usize) {
+		let (obstacle, placed, nodes) = match self.timetable.obstacles[i] {
+			Some(ref entry) => (entry.obstacle, entry.placed, entry.nodes.clone()),
+			None => return,
+		};
+		let blocked = if placed { clip_to_map(self.map, obstacle).and_then(|area| self.edges_through(area, &nodes)) } else { None };
+		match blocked {
+			Some(blocked) => {
+				self.push_obstacle(obstacle);
+				// Nodes inside of the obstacle are kept without any edges, they are needed again once it is disabled
+				self.cut_edges(&blocked);
+				self.seal_touching_nodes(obstacle, &nodes);
+			}
+			None => {
+				let (x,y,w,h) = obstacle;
+				self.add_obstacle(x,y,w,h);
+				if let Some(ref mut entry) = self.timetable.obstacles[i] { entry.placed = true; }
+			}
+		}
+		self.track_managed_nodes();
+	}
+
+	// Frees the area of the managed obstacle with index i in the timetable. The nodes that are still in the graph from the last time
+	// the obstacle was disabled are connected again as they were, anything that is missing is linked like after remove_obstacle().
+	// after_update() has to be called afterwards.
+	pub(crate) fn disable_obstacle(&mut self, i: usize) {
+		let (obstacle, nodes) = match self.timetable.obstacles[i] {
+			Some(ref entry) => (entry.obstacle, entry.nodes.clone()),
+			None => return,
+		};
+		match self.obstacles.iter().position(|&o| o == obstacle) {
+			Some(j) => { self.swap_remove_obstacle(j); }
+			None => return,
+		}
+		if let Some(area) = clip_to_map(self.map, obstacle) {
+			self.free_area(area, &nodes);
+		}
+		self.track_managed_nodes();
+	}
+
+	// Same as disable_obstacle() for an obstacle that is not managed by the timetable, like the blockers of the map border.
+	// Its nodes are searched in the whole graph.
+	pub(crate) fn disable_blocker(&mut self, obstacle: (S,S,S,S)) {
+		match self.obstacles.iter().position(|&o| o == obstacle) {
+			Some(j) => { self.swap_remove_obstacle(j); }
+			None => return,
+		}
+		if let Some(area) = clip_to_map(self.map, obstacle) {
+			let nodes: Vec<usize> = (0..self.graph.len()).filter(|&n| self.node_is_inside(n, area)).collect();
+			self.free_area(area, &nodes);
+		}
+	}
+
+	// Deletes the edges returned by blocked_edges() and recomputes the paths that went through them
+	pub(crate) fn cut_edges(&mut self, blocked: &[(usize, usize, usize)]) {
+		for &(n, direction, neighbour) in blocked.iter() {
+			self.graph[n].delete_neighbour(neighbour);
+			self.graph[neighbour].delete_neighbour(n);
+			if self.graph[n].shortest_path == Some(direction) {
+				self.invalidate_paths_through_node(n);
+			}
+			if self.graph[neighbour].shortest_path == Some((direction + 2) % 4) {
+				self.invalidate_paths_through_node(neighbour);
+			}
+		}
+		self.update();
+	}
+
+	// Updates the node lists of all managed obstacles with the nodes that have been changed since the last after_update()
+	pub(crate) fn track_managed_nodes(&mut self) {
+		if self.timetable.obstacles.iter().all(|entry| entry.is_none()) { return; }
+		let changed = self.graph.touched().to_vec();
+		let live = self.graph.len();
+		let map = self.map;
+		for entry in self.timetable.obstacles.iter_mut().filter_map(|entry| entry.as_mut()) {
+			let area = match clip_to_map(map, entry.obstacle) {
+				Some(area) => area,
+				None => continue,
+			};
+			for &n in changed.iter() {
+				let inside = n < live && self.graph[n].x != S::NEG_INFINITY && is_inside(&self.graph[n], area);
+				match entry.nodes.binary_search(&n) {
+					Ok(k) => if !inside { entry.nodes.remove(k); },
+					Err(k) => if inside { entry.nodes.insert(k, n); },
+				}
+			}
+		}
+	}
+
+	// The edges of the nodes that go through the area as (node, direction, neighbour).
+	// Returns None if the nodes around the area are missing, i.e. a corner has no node or an edge leaves it without ending on its border.
+	fn edges_through(&self, (x,y,w,h): (S,S,S,S), nodes: &[usize]) -> Option<Vec<(usize, usize, usize)>> {
+		for &(cx, cy) in [(x,y), (x+w,y), (x+w,y+h), (x,y+h)].iter() {
+			if !nodes.iter().any(|&n| self.graph[n].x == cx && self.graph[n].y == cy) && !self.coordinate_is_blocked(cx, cy) {
+				return None;
+			}
+		}
+		let mut blocked = Vec::new();
+		for &n in nodes.iter() {
+			let (nx, ny) = (self.graph[n].x, self.graph[n].y);
+			for direction in 0..4 {
+				let neighbour = match self.graph[n].neighbours[direction] {
+					Some(neighbour) => neighbour,
+					None => continue,
+				};
+				let (mx, my) = (self.graph[neighbour].x, self.graph[neighbour].y);
+				let through = if direction == NORTH || direction == SOUTH {
+					v_line_overlaps_obstacle( (nx, ny.min(my), ny.max(my)), (x,y,w,h) )
+				}
+				else {
+					h_line_overlaps_obstacle( (nx.min(mx), ny, nx.max(mx)), (x,y,w,h) )
+				};
+				if !through { continue; }
+				if nodes.binary_search(&neighbour).is_err() { return None; }
+				if direction == NORTH || direction == EAST { blocked.push((n, direction, neighbour)); }
+			}
+		}
+		Some(blocked)
+	}
+
+	// Links the given nodes inside of the area and on its border again after an obstacle has been taken away from it.
+	// Nodes on the same line through the inside are connected where nothing else blocks them, nodes are added where these lines cross,
+	// and lines that do not end on a node are continued like after remove_obstacle().
+	fn free_area(&mut self, (x,y,w,h): (S,S,S,S), nodes: &[usize]) {
+		let mut nodes: Vec<usize> = nodes.iter().cloned().filter(|&n| self.graph[n].x != S::NEG_INFINITY).collect();
+		let inside_x = |v: S| v > x && v < x + w;
+		let inside_y = |v: S| v > y && v < y + h;
+
+		// lines through the inside of the area
+		let mut columns: Vec<S> = nodes.iter().map(|&n| self.graph[n].x).filter(|&v| inside_x(v)).collect();
+		let mut rows: Vec<S> = nodes.iter().map(|&n| self.graph[n].y).filter(|&v| inside_y(v)).collect();
+		for line in [&mut columns, &mut rows].iter_mut() {
+			line.sort_by(|a, b| a.partial_cmp(b).unwrap_or(Ordering::Equal));
+			line.dedup();
+		}
+		// nodes where they cross
+		let mut positions: Vec<(S, S)> = nodes.iter().map(|&n| (self.graph[n].x, self.graph[n].y)).collect();
+		positions.sort_by(compare_positions);
+		let mut added = Vec::new();
+		for &cx in columns.iter() {
+			for &cy in rows.iter() {
+				if positions.binary_search_by(|p| compare_positions(p, &(cx, cy))).is_ok() || self.coordinate_is_blocked(cx, cy) { continue; }
+				added.push(self.graph.len());
+				self.graph.push(Box::new(GraphNode::new(cx, cy)));
+			}
+		}
+		nodes.extend(added.iter().cloned());
+
+		// neighbours on the same line
+		let mut by_row = nodes.clone();
+		by_row.sort_by(|&a, &b| compare_positions(&(self.graph[a].y, self.graph[a].x), &(self.graph[b].y, self.graph[b].x)));
+		for pair in by_row.windows(2) {
+			if self.graph[pair[0]].y == self.graph[pair[1]].y && inside_y(self.graph[pair[0]].y) {
+				self.connect_free(pair[0], EAST, pair[1]);
+			}
+		}
+		let mut by_column = nodes.clone();
+		by_column.sort_by(|&a, &b| compare_positions(&(self.graph[a].x, self.graph[a].y), &(self.graph[b].x, self.graph[b].y)));
+		for pair in by_column.windows(2) {
+			if self.graph[pair[0]].x == self.graph[pair[1]].x && inside_x(self.graph[pair[0]].x) {
+				self.connect_free(pair[0], SOUTH, pair[1]);
+			}
+		}
+
+		// lines that still end inside of the area, only happens the first time the area is freed
+		for &n in nodes.iter() {
+			let (nx, ny) = (self.graph[n].x, self.graph[n].y);
+			if inside_x(nx) {
+				if ny < y + h && self.graph[n].neighbours[SOUTH].is_none() && self.v_line_overlaps_no_obstacle(nx, ny, ny + S::EPS) {
+					self.link_to_south(n);
+				}
+				if ny > y && self.graph[n].neighbours[NORTH].is_none() && self.v_line_overlaps_no_obstacle(nx, ny - S::EPS, ny) {
+					self.link_to_north(n);
+				}
+			}
+			if inside_y(ny) {
+				if nx < x + w && self.graph[n].neighbours[EAST].is_none() && self.h_line_overlaps_no_obstacle(nx, ny, nx + S::EPS) {
+					self.link_to_east(n);
+				}
+				if nx > x && self.graph[n].neighbours[WEST].is_none() && self.h_line_overlaps_no_obstacle(nx - S::EPS, ny, nx) {
+					self.link_to_west(n);
+				}
+			}
+		}
+
+		// A node can be linked from the opposite side before it has a cost itself,
+		// therefore the costs are only updated once all nodes are linked
+		for &n in nodes.iter() {
+			self.update_node(n);
+			self.update_neighbours(n);
+		}
+		// crossings that none of the lines reach
+		for &n in added.iter() {
+			if self.graph[n].neighbours == [None, None, None, None] {
+				self.graph[n].x = S::NEG_INFINITY;
+				self.graph[n].y = S::NEG_INFINITY;
+				self.graph[n].cost = S::INFINITY;
+				self.dead_nodes.push(n);
+			}
+		}
+		self.swap_out_dead_nodes();
+	}
+
+	// Connects the node to the neighbour in the given direction, unless one of them is already connected there or the line between them is blocked
+	fn connect_free(&mut self, n: usize, direction: usize, neighbour: usize) {
+		let opposite = (direction + 2) % 4;
+		if self.graph[n].neighbours[direction].is_some() || self.graph[neighbour].neighbours[opposite].is_some() { return; }
+		let (x, y) = (self.graph[n].x, self.graph[n].y);
+		let free = if direction == EAST { self.h_line_overlaps_no_obstacle(x, y, self.graph[neighbour].x) }
+			else { self.v_line_overlaps_no_obstacle(x, y, self.graph[neighbour].y) };
+		if free {
+			self.graph[n].neighbours[direction] = Some(neighbour);
+			self.graph[neighbour].neighbours[opposite] = Some(n);
+		}
+	}
+
+	// All edges that go through the obstacle as (node, direction, neighbour)
+	pub(crate) fn blocked_edges(&self, obstacle: (S,S,S,S)) -> Vec<(usize, usize, usize)> {
+		let mut blocked = Vec::new();
+		for n in 0..self.graph.len() {
+			if let Some(up) = self.graph[n].neighbours[NORTH] {
+				if v_line_overlaps_obstacle( (self.graph[n].x, self.graph[up].y, self.graph[n].y), obstacle ) {
+					blocked.push((n, NORTH, up));
+				}
+			}
+			if let Some(right) = self.graph[n].neighbours[EAST] {
+				if h_line_overlaps_obstacle( (self.graph[n].x, self.graph[n].y, self.graph[right].x), obstacle ) {
+					blocked.push((n, EAST, right));
+				}
+			}
+		}
+		blocked
+	}
+
+	// Checks whether the node lies inside of the obstacle or on its border
+	fn node_is_inside(&self, n: usize, obstacle: (S,S,S,S)) -> bool {
+		is_inside(&self.graph[n], obstacle)
+	}
+}
+
+// Cuts the obstacle at the map boundary the same way insert_obstacle() does
+// Returns None if the obstacle lies completely outside of the map
+fn clip_to_map<S: Scalar>((mx, my, mw, mh): (S,S,S,S), (x,y,w,h): (S,S,S,S)) -> Option<(S,S,S,S)> {
+	if x > mx + mw || y > my + mh { return None; }
+	let w = if x + w > mx + mw { mx + mw - x + S::from_f64(4.0) * S::EPS } else { w };
+	let h = if y + h > my + mh { my + mh - y + S::from_f64(4.0) * S::EPS } else { h };
+	Some((x,y,w,h))
+}
+
+fn is_inside<S: Scalar>(node: &GraphNode<S>, (x,y,w,h): (S,S,S,S)) -> bool {
+	node.x >= x && node.x <= x + w && node.y >= y && node.y <= y + h
+}
+
+// Orders positions by their first coordinate, then by the second one
+fn compare_positions<S: Scalar>(a: &(S, S), b: &(S, S)) -> Ordering {
+	a.0.partial_cmp(&b.0).unwrap_or(Ordering::Equal).then(a.1.partial_cmp(&b.1).unwrap_or(Ordering::Equal))
+}
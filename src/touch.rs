@@ -0,0 +1,172 @@
+//! Whether two obstacles that only touch each other leave a gap between them
+
+use super::{JkmShortestPathMap, Scalar, NORTH, EAST};
+use border::BorderMode;
+
+/// How borders that two obstacles share are treated, see JkmShortestPathMap::set_touch_policy()
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum TouchPolicy {
+	/// Paths can go between touching obstacles, along the shared border and through corners where they meet
+	Open,
+	/// Touching obstacles form one wall, the shared border and corners where two obstacles meet diagonally are blocked
+	Closed,
+}
+
+impl<S: Scalar> JkmShortestPathMap<S> {
+
+	/// Chooses whether obstacles that touch without overlapping block the path between them. The default is TouchPolicy::Open.
+	/// Changing it removes and inserts again all obstacles that touch another one, so that the graph follows the new policy.
+	/// This costs as much as inserting these obstacles, obstacles that touch no other one are left as they are.
+	pub fn set_touch_policy(&mut self, policy: TouchPolicy) {
+		if self.touch == policy { return; }
+		let border = self.border;
+		self.set_border(BorderMode::Open, BorderMode::Open, BorderMode::Open, BorderMode::Open);
+		// the space between layers stays blocked, remove_obstacle() skips it
+		let obstacles: Vec<(S,S,S,S)> = self.obstacles.iter().cloned()
+			.filter(|&o| !self.layer_blockers.contains(&o) && self.seams.involve(o))
+			.collect();
+		for &(x, y, w, h) in obstacles.iter().rev() {
+			self.remove_obstacle(x, y, w, h);
+		}
+		self.touch = policy;
+		for &(x, y, w, h) in obstacles.iter() {
+			self.insert_obstacle(x, y, w, h);
+		}
+		self.set_border(border[0], border[1], border[2], border[3]);
+	}
+
+	/// Returns the policy chosen with set_touch_policy()
+	pub fn touch_policy(&self) -> TouchPolicy {
+		self.touch
+	}
+
+	// With TouchPolicy::Closed, cuts the edges around the obstacle that lie between it and another obstacle touching it.
+	// The nodes on the sealed border stay in the graph without these edges.
+	pub(crate) fn seal_touching(&mut self, obstacle: (S,S,S,S)) {
+		if self.touch == TouchPolicy::Open { return; }
+		let nodes: Vec<usize> = (0..self.graph.len()).collect();
+		self.seal_touching_nodes(obstacle, &nodes);
+	}
+
+	// Same as seal_touching(), but only looks at the edges of the given nodes
+	pub(crate) fn seal_touching_nodes(&mut self, (x,y,w,h): (S,S,S,S), nodes: &[usize]) {
+		if self.touch == TouchPolicy::Open { return; }
+		let mut sealed = Vec::new();
+		for &n in nodes.iter() {
+			for direction in 0..4 {
+				let neighbour = match self.graph[n].neighbours[direction] {
+					Some(neighbour) => neighbour,
+					None => continue,
+				};
+				// each edge is looked at from its lower or its left node
+				let (n, direction, neighbour) = if direction == NORTH || direction == EAST { (n, direction, neighbour) } else { (neighbour, (direction + 2) % 4, n) };
+				let (nx, ny) = (self.graph[n].x, self.graph[n].y);
+				let is_sealed = if direction == NORTH {
+					let top = self.graph[neighbour].y;
+					nx >= x && nx <= x + w && top <= y + h && ny >= y && !self.v_line_overlaps_no_obstacle(nx, top, ny)
+				}
+				else {
+					let end = self.graph[neighbour].x;
+					ny >= y && ny <= y + h && nx <= x + w && end >= x && !self.h_line_overlaps_no_obstacle(nx, ny, end)
+				};
+				if is_sealed && !sealed.contains(&(n, direction, neighbour)) {
+					sealed.push((n, direction, neighbour));
+				}
+			}
+		}
+		if !sealed.is_empty() {
+			self.cut_edges(&sealed);
+		}
+	}
+}
+
+// Gives the extent of an obstacle along a line and across it
+pub(crate) type Sides<S> = fn((S,S,S,S)) -> (S,S,S,S);
+
+// Extent of an obstacle along a horizontal line and across it: (left, right, top, bottom)
+pub(crate) fn horizontal<S: Scalar>((x,y,w,h): (S,S,S,S)) -> (S,S,S,S) { (x, x + w, y, y + h) }
+
+// Extent of an obstacle along a vertical line and across it: (top, bottom, left, right)
+pub(crate) fn vertical<S: Scalar>((x,y,w,h): (S,S,S,S)) -> (S,S,S,S) { (y, y + h, x, x + w) }
+
+// A border shared by two touching obstacles on the line at `at`, from `from` to `to` along it.
+// If both ends are the same, it is a corner where the two obstacles meet diagonally.
+#[derive(Copy, Clone, Debug)]
+struct Seam<S> {
+	at: S,
+	from: S,
+	to: S,
+	obstacles: [(S,S,S,S); 2],
+}
+
+// All borders and corners that obstacles share, found once when an obstacle is inserted or removed,
+// so that testing a line against them does not have to look at each pair of obstacles.
+#[derive(Clone, Debug)]
+pub(crate) struct Seams<S> {
+	// on horizontal lines, `at` is a y coordinate
+	horizontal: Vec<Seam<S>>,
+	// on vertical lines, `at` is an x coordinate
+	vertical: Vec<Seam<S>>,
+}
+
+impl<S: Scalar> Seams<S> {
+	pub(crate) fn new() -> Seams<S> {
+		Seams { horizontal: Vec::new(), vertical: Vec::new() }
+	}
+
+	// Adds the seams between the obstacle and the others
+	pub(crate) fn add(&mut self, obstacle: (S,S,S,S), others: &[(S,S,S,S)]) {
+		for &other in others.iter() {
+			for &(a, b) in [(obstacle, other), (other, obstacle)].iter() {
+				self.horizontal.extend(seam(a, b, horizontal));
+				self.vertical.extend(seam(a, b, vertical));
+			}
+		}
+	}
+
+	// Whether the obstacle shares a border or a corner with another one
+	pub(crate) fn involve(&self, obstacle: (S,S,S,S)) -> bool {
+		self.horizontal.iter().chain(self.vertical.iter()).any(|seam| seam.obstacles.contains(&obstacle))
+	}
+
+	// Removes all seams of the obstacle
+	pub(crate) fn remove(&mut self, obstacle: (S,S,S,S)) {
+		self.horizontal.retain(|seam| !seam.obstacles.contains(&obstacle));
+		self.vertical.retain(|seam| !seam.obstacles.contains(&obstacle));
+	}
+
+	// Whether the horizontal line from x0 to x1 runs along a seam or through a corner, including its ends
+	pub(crate) fn seal_h_line(&self, x0: S, y: S, x1: S) -> bool {
+		line_is_sealed(&self.horizontal, x0, x1, y)
+	}
+
+	// Whether the vertical line from y0 to y1 runs along a seam or through a corner, including its ends
+	pub(crate) fn seal_v_line(&self, x: S, y0: S, y1: S) -> bool {
+		line_is_sealed(&self.vertical, y0, y1, x)
+	}
+
+	// Whether the coordinate lies on the inner part of a seam or on a corner
+	pub(crate) fn seal_coordinate(&self, x: S, y: S) -> bool {
+		point_is_sealed(&self.horizontal, x, y) || point_is_sealed(&self.vertical, y, x)
+	}
+}
+
+// The seam where the side of `a` at its end across the line meets the side of `b` at its start
+fn seam<S: Scalar>(a: (S,S,S,S), b: (S,S,S,S), sides: Sides<S>) -> Option<Seam<S>> {
+	let (a0, a1, _, a_end) = sides(a);
+	let (b0, b1, b_start, _) = sides(b);
+	if a_end != b_start { return None; }
+	let (from, to) = if a0.max(b0) < a1.min(b1) { (a0.max(b0), a1.min(b1)) }
+		else if a1 == b0 { (a1, a1) }
+		else if b1 == a0 { (a0, a0) }
+		else { return None; };
+	Some(Seam { at: a_end, from, to, obstacles: [a, b] })
+}
+
+fn line_is_sealed<S: Scalar>(seams: &[Seam<S>], from: S, to: S, at: S) -> bool {
+	seams.iter().any(|seam| seam.at == at && if seam.from < seam.to { from.max(seam.from) < to.min(seam.to) } else { from <= seam.from && seam.from <= to })
+}
+
+fn point_is_sealed<S: Scalar>(seams: &[Seam<S>], along: S, at: S) -> bool {
+	seams.iter().any(|seam| seam.at == at && if seam.from < seam.to { seam.from < along && along < seam.to } else { along == seam.from })
+}
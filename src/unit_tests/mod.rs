@@ -11,15 +11,27 @@ const WEST: usize = 3;
 
 mod tests;
 
-pub fn check_module_invariants (testee: &JkmShortestPathMap) {
-	inv_neighbours_are_symmetric(testee);
-	inv_neighbours_are_alligned(testee);
-	inv_all_shortest_paths_lead_to_destination(testee);	
-	inv_costs_are_correct(testee);
+pub fn check_module_invariants<S: Scalar>(testee: &JkmShortestPathMap<S>) {
+	if let Err(e) = testee.check_graph() {
+		print_graph(testee);
+		panic!("{}", e);
+	}
+	inv_stored_clearance_is_correct(testee);
+}
+
+// Invariants of what is derived from the graph at the end of each update, they do not hold while the graph is changed
+pub fn check_update_invariants<S: Scalar>(testee: &JkmShortestPathMap<S>) {
+	check_module_invariants(testee);
+	if let Err(e) = testee.check_regions() {
+		print_graph(testee);
+		panic!("{}", e);
+	}
+	inv_all_nodes_are_indexed(testee);
+	inv_portal_ends_are_stored(testee);
 }
 	
 	// Call in assertion with ||
-pub fn print_graph (testee: &JkmShortestPathMap) -> bool {
+pub fn print_graph<S: Scalar>(testee: &JkmShortestPathMap<S>) -> bool {
 	
 	log_map(testee, "log".to_string());
 	
@@ -40,13 +52,13 @@ pub fn print_graph (testee: &JkmShortestPathMap) -> bool {
 	false
 }
 
-pub fn dummy_logger (testee: &JkmShortestPathMap, name: String ) -> bool {
+pub fn dummy_logger<S: Scalar>(testee: &JkmShortestPathMap<S>, name: String ) -> bool {
 	print_graph(testee);
 	log_map(&testee, name);
 	false
 }
 
-pub fn log_map(testee: &JkmShortestPathMap, name: String ) {
+pub fn log_map<S: Scalar>(testee: &JkmShortestPathMap<S>, name: String ) {
 	if let Ok(mut f) = File::create("log/".to_string() + &name + ".jkmmap") {
 		//nodes
 		for i in 0..testee.graph.len() {
@@ -80,80 +92,53 @@ pub fn log_map(testee: &JkmShortestPathMap, name: String ) {
 }
 
 	
-fn inv_neighbours_are_symmetric(testee: &JkmShortestPathMap) {
+fn inv_stored_clearance_is_correct<S: Scalar>(testee: &JkmShortestPathMap<S>) {
+	if !testee.clearance_tracked { return; }
 	for (i, node) in testee.graph.iter().enumerate() {
-		if let Some(neighbour) = node.neighbours[NORTH] {
-			assert!(testee.graph[neighbour].neighbours[SOUTH] == Some(i)|| print_graph(testee), "\nNode #{} has a neighbour in the north, Node #{}, but the neighbourhood is not returned.\n", i, neighbour );
-		}
-		if let Some(neighbour) = node.neighbours[EAST] {
-			assert!(testee.graph[neighbour].neighbours[WEST] == Some(i)|| print_graph(testee), "\nNode #{} has a neighbour in the east, Node #{}, but the neighbourhood is not returned.\n", i, neighbour  );
-		}
-		if let Some(neighbour) = node.neighbours[SOUTH] {
-			assert!(testee.graph[neighbour].neighbours[NORTH] == Some(i)|| print_graph(testee), "\nNode #{} has a neighbour in the south, Node #{}, but the neighbourhood is not returned.\n", i, neighbour  );
+		if let Some(up) = node.neighbours[NORTH] {
+			let width = clearance::corridor_width(&testee.obstacles, testee.map, testee.graph[up].y, node.y, node.x, touch::vertical);
+			assert!( node.clearance[NORTH].0 != testee.graph[up].y || node.clearance[NORTH].1 == width || print_graph(testee),
+			"The clearance of the edge from node #{} to the north is {:?}, expected {}.", i, node.clearance[NORTH], width);
 		}
-		if let Some(neighbour) =node.neighbours[WEST] {
-			assert!(testee.graph[neighbour].neighbours[EAST] == Some(i) || print_graph(testee), "\nNode #{} has a neighbour in the west, Node #{}, but the neighbourhood is not returned.\n", i, neighbour  );
+		if let Some(right) = node.neighbours[EAST] {
+			let width = clearance::corridor_width(&testee.obstacles, testee.map, node.x, testee.graph[right].x, node.y, touch::horizontal);
+			assert!( node.clearance[EAST].0 != testee.graph[right].x || node.clearance[EAST].1 == width || print_graph(testee),
+			"The clearance of the edge from node #{} to the east is {:?}, expected {}.", i, node.clearance[EAST], width);
 		}
 	}
 }
 
-fn inv_neighbours_are_alligned (testee: &JkmShortestPathMap) {
-	for (i, node) in testee.graph.iter().enumerate() {
-		if let Some(neighbour) = node.neighbours[NORTH] {
-			assert!( testee.graph[neighbour].x == node.x && testee.graph[neighbour].y < node.y || dummy_logger(testee, "neighbours_not_alligned_log".to_string()) ,"Neighbours are not alligend correctly: Node#{} and Node#{}", i, neighbour );
-		}
-		if let Some(neighbour) = node.neighbours[EAST] {
-			assert!( testee.graph[neighbour].y == node.y && testee.graph[neighbour].x > node.x || dummy_logger(testee, "neighbours_not_alligned_log".to_string()),"Neighbours are not alligend correctly: Node#{} and Node#{}", i, neighbour );
-		}
-		if let Some(neighbour) = node.neighbours[SOUTH] {
-			assert!( testee.graph[neighbour].x == node.x && testee.graph[neighbour].y > node.y || dummy_logger(testee, "neighbours_not_alligned_log".to_string()),"Neighbours are not alligend correctly: Node#{} and Node#{}", i, neighbour );
+// Every portal in the graph knows the nodes of its ends
+fn inv_portal_ends_are_stored<S: Scalar>(testee: &JkmShortestPathMap<S>) {
+	let mut ends = 0;
+	for portal in testee.portals.iter() {
+		if let Some((a, b)) = portal.ends {
+			assert!((testee.graph[a].x, testee.graph[a].y) == portal.from && (testee.graph[b].x, testee.graph[b].y) == portal.to, "Portal ends #{} and #{} have moved.", a, b);
+			assert!(testee.graph[a].portal.map(|end| end.other) == Some(b), "Portal end #{} is not linked to #{}.", a, b);
+			ends += 2;
 		}
-		if let Some(neighbour) =node.neighbours[WEST] {
-			assert!( testee.graph[neighbour].y == node.y && testee.graph[neighbour].x < node.x || dummy_logger(testee, "neighbours_not_alligned_log".to_string()),"Neighbours are not alligend correctly: Node#{} and Node#{}", i, neighbour );
-		}
-	}
-}
-
-fn inv_all_shortest_paths_lead_to_destination(testee: &JkmShortestPathMap) {
-	for i in 0..testee.graph.len() {
-		assert!(shortest_path_leads_to_index(testee, i, testee.end_point_index, testee.graph.len())|| print_graph(testee) );
 	}
+	assert_eq!(ends, testee.graph.iter().filter(|node| node.portal.is_some()).count(), "Some portal ends are not stored.");
 }
 
-fn inv_costs_are_correct(testee: &JkmShortestPathMap){
-	for i in 0..testee.graph.len() {
-		if let Some(sp) = testee.graph[i].shortest_path {
-			if let Some(neighbour) = testee.graph[i].neighbours[sp] {
-				let expected_cost = testee.graph[neighbour].cost + (testee.graph[i].x - testee.graph[neighbour].x).abs() + (testee.graph[i].y - testee.graph[neighbour].y).abs();
-				assert!( testee.graph[i].cost == expected_cost || print_graph(testee), 
-				" The expected cost for node #{} was {} but it has the cost {}. ",
-				i, expected_cost, testee.graph[i].cost);
-			}
+fn inv_all_nodes_are_indexed<S: Scalar>(testee: &JkmShortestPathMap<S>) {
+	for (i, node) in testee.graph.iter().enumerate() {
+		if node.x == S::NEG_INFINITY { continue; }
+		assert!( testee.indexed_node(node.x, node.y).is_some() || print_graph(testee), "Node #{} at [{}|{}] can not be found in the index.", i, node.x, node.y);
+		// from the middle of an edge, the closest edge across it lies on the same line
+		if let Some(bot) = node.neighbours[SOUTH] {
+			let middle = (node.y + testee.graph[bot].y) / S::from_f64(2.0);
+			assert!( testee.nearest_edge(node.x, middle, WEST).map(|(_, x)| x) == Some(node.x) || print_graph(testee),
+			"The edge from node #{} to the south can not be found in the index.", i);
 		}
-	}
-}
-
-fn shortest_path_leads_to_index(testee: &JkmShortestPathMap, start: usize, end: usize, allowed_calls: usize) -> bool {
-	if start == end { true }
-	else if allowed_calls == 0 {  println!("No way to get from Node #{} to Node #{}.", start, end); false }
-	else { 
-		if let Some(sp) = testee.graph[start].shortest_path {
-			if let Some(next) =  testee.graph[start].neighbours[sp] {
-				shortest_path_leads_to_index(testee, next, end, allowed_calls-1)
-			}
-			else { panic!("There was a shortest path marked from node {} in direction {} but there was no neighbour in this direction!\n", start, sp); }
+		if let Some(right) = node.neighbours[EAST] {
+			let middle = (node.x + testee.graph[right].x) / S::from_f64(2.0);
+			assert!( testee.nearest_edge(middle, node.y, NORTH).map(|(_, y)| y) == Some(node.y) || print_graph(testee),
+			"The edge from node #{} to the east can not be found in the index.", i);
 		}
-		else { true }
-	}		
+	}
 }
 
-/* TODO
-fn inv_marked_paths_are_the_shortest_within_the_graph(testee: &JkmShortestPathMap) {
-	//Djikstra
-	let mut queue = std::collections::BinaryHeap::new();
-	MinSortableEdge ((,),)
-	queue.push(  )
-}*/
 
 #[test]
 fn simple_creation_test() {
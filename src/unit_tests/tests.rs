@@ -404,7 +404,1040 @@ fn delete_test_one() {
 }
 
 
+// All node values of the graph together with the obstacles and the start and end point
+type GraphState = (Vec<(f64, f64, [Option<usize>;4], Option<usize>, f64)>, Vec<(f64,f64,f64,f64)>, usize, usize);
+
+// Lists the state of the map, used to compare two states of the map
+fn graph_state(spm: &JkmShortestPathMap) -> GraphState {
+	let nodes = spm.graph.iter().map(|n| (n.x, n.y, n.neighbours, n.shortest_path, n.cost)).collect();
+	(nodes, spm.obstacles.clone(), spm.start_point_index, spm.end_point_index)
+}
+
+#[test]
+fn rollback_restores_checkpoint() {
+	let start = (500.0, 0.0);
+	let end = (500.0, 1000.0);
+	let map = (0.0, 0.0, 1000.0, 1000.0);
+	let mut spm = JkmShortestPathMap::new(start, end, map);
+	spm.add_map_border();
+	spm.insert_obstacle(200.0, 300.0, 400.0, 100.0);
+	check_module_invariants(&spm);
+	
+	let before = graph_state(&spm);
+	let token = spm.checkpoint();
+	spm.insert_obstacle(300.0, 200.0, 400.0, 300.0);
+	spm.insert_obstacle(-20.0, 600.0, 700.0, 50.0);
+	spm.remove_obstacle(200.0, 300.0, 400.0, 100.0);
+	check_module_invariants(&spm);
+	
+	let inner = spm.checkpoint();
+	let between = graph_state(&spm);
+	spm.insert_obstacle(650.0, 210.0, 400.0, 280.0);
+	assert!(spm.rollback(inner));
+	assert!(graph_state(&spm) == between || print_graph(&spm), "Rolling back to the inner checkpoint did not restore the graph.");
+	
+	assert!(spm.rollback(token));
+	assert!(graph_state(&spm) == before || print_graph(&spm), "Rolling back to the outer checkpoint did not restore the graph.");
+	assert!(!spm.rollback(inner), "The inner checkpoint should have been discarded by rolling back to the outer one.");
+	check_module_invariants(&spm);
+	
+	// the token stays valid after a rollback
+	spm.insert_obstacle(650.0, 210.0, 400.0, 280.0);
+	assert!(spm.rollback(token));
+	assert!(graph_state(&spm) == before || print_graph(&spm));
+	
+	assert!(spm.commit(token));
+	assert!(!spm.rollback(token));
+	spm.insert_obstacle(650.0, 210.0, 400.0, 280.0);
+	check_module_invariants(&spm);
+}
+
+#[test]
+fn clone_and_snapshot_are_independent() {
+	let start = (240.0, 0.0);
+	let end = (240.0, 700.0);
+	let map = (0.0, 0.0, 500.0, 700.0);
+	let mut spm = JkmShortestPathMap::new(start, end, map);
+	spm.add_map_border();
+	spm.insert_obstacle(20.0, 300.0, 300.0, 70.0);
+	spm.insert_obstacle(250.0, 340.0, 70.0, 70.0);
+	
+	let snapshot = spm.snapshot();
+	let mut hypothetical = spm.clone();
+	hypothetical.insert_obstacle(300.0, 280.0, 250.0, 70.0);
+	check_module_invariants(&hypothetical);
+	assert!(graph_state(&hypothetical) != graph_state(&spm));
+	assert!(!format!("{:?}", spm).is_empty());
+	
+	let test_cases = [ (start.0, start.1), (460.0, 121.2), (283.7, 318.5), (0.0, 50.0), (240.0, 100.0) ];
+	for &(x, y) in test_cases.iter() {
+		assert_eq!(snapshot.nearest_checkpoint(x, y), spm.nearest_checkpoint(x, y));
+		assert_eq!(snapshot.next_checkpoint(x, y), spm.next_checkpoint(x, y));
+	}
+	assert_eq!(snapshot.destination(), end);
+	
+	spm.insert_obstacle(300.0, 280.0, 250.0, 70.0);
+	let (x, y) = snapshot.node_position(0);
+	assert!(snapshot.node_count() > 0 && x == map.0 && y == map.1);
+	for n in 0..snapshot.node_count() {
+		if let Some(next) = snapshot.next_node(n) {
+			let (x0, y0) = snapshot.node_position(n);
+			let (x1, y1) = snapshot.node_position(next);
+			assert_eq!(snapshot.node_cost(n), snapshot.node_cost(next) + (x0 - x1).abs() + (y0 - y1).abs());
+			assert!(snapshot.node_neighbours(n).contains(&Some(next)));
+		}
+	}
+}
+
+#[test]
+fn concurrent_queries_during_updates() {
+	fn assert_send_sync<T: Send + Sync>() {}
+	assert_send_sync::<JkmShortestPathMap>();
+	assert_send_sync::<PathSnapshot>();
+	assert_send_sync::<SharedPathMap>();
+	
+	let start = (240.0, 0.0);
+	let end = (240.0, 700.0);
+	let map = (0.0, 0.0, 500.0, 700.0);
+	let shared = std::sync::Arc::new(SharedPathMap::new(JkmShortestPathMap::new(start, end, map)));
+	shared.update(|spm| spm.add_map_border());
+	
+	let readers: Vec<_> = (0..4).map(|_| {
+		let shared = shared.clone();
+		std::thread::spawn(move || {
+			for _ in 0..20 {
+				let spm = shared.read();
+				check_module_invariants(&spm);
+				assert!(spm.next_checkpoint(start.0, start.1).is_some());
+				assert!(!spm.coordinate_is_blocked(start.0, start.1));
+			}
+		})
+	}).collect();
+	
+	let obstacles = [ (20.0, 300.0, 70.0, 70.0), (120.0, 320.0, 70.0, 70.0), (250.0, 290.0, 70.0, 70.0), (400.0, 295.0, 70.0, 70.0) ];
+	for &(x,y,w,h) in obstacles.iter() {
+		shared.write(move |spm| spm.insert_obstacle(x,y,w,h));
+		assert!(!shared.read().coordinate_is_blocked(x + 1.0, y + 1.0));
+		shared.publish();
+		assert!(shared.read().coordinate_is_blocked(x + 1.0, y + 1.0));
+	}
+	// the back buffer has caught up with the published changes
+	let back = shared.write(|spm| (spm.obstacles.clone(), spm.graph.len()));
+	assert_eq!(back, (shared.read().obstacles.clone(), shared.read().graph.len()));
+	for reader in readers {
+		reader.join().unwrap();
+	}
+}
+
+#[test]
+fn would_block_does_not_change_the_map() {
+	let start = (100.0, 0.0);
+	let end = (100.0, 300.0);
+	let map = (0.0,0.0,200.0,300.0);
+	let mut spm = JkmShortestPathMap::new(start, end, map);
+	spm.add_map_border();
+	spm.insert_obstacle(80.0,20.0,40.0, 20.0);
+	let before = graph_state(&spm);
+	let cost = spm.graph[spm.start_point_index].cost;
+	
+	// not on the current path
+	let impact = spm.would_block(140.0, 40.0, 50.0, 110.0);
+	assert!(impact.start_reachable && impact.cost_before == cost && impact.cost_increase() == 0.0);
+	
+	// on the current path
+	let impact = spm.would_block(20.0, 265.0, 230.0, 20.0);
+	assert!(impact.start_reachable && impact.cost_before == cost);
+	assert!(graph_state(&spm) == before);
+	spm.insert_obstacle(20.0, 265.0, 230.0, 20.0);
+	assert_eq!(impact.cost_after, spm.graph[spm.start_point_index].cost);
+	assert!(impact.cost_increase() > 0.0);
+	spm.remove_obstacle(20.0, 265.0, 230.0, 20.0);
+	
+	// cuts the map in two
+	let before_wall = graph_state(&spm);
+	let impact = spm.would_block(-1.0, 150.0, 202.0, 20.0);
+	assert!(!impact.start_reachable && impact.cost_after == f64::INFINITY);
+	assert!(graph_state(&spm) == before_wall);
+	
+	// same cost as inserting the obstacle, also when it touches another one
+	spm.insert_obstacle(20.0, 120.0, 60.0, 30.0);
+	for &policy in [TouchPolicy::Open, TouchPolicy::Closed].iter() {
+		spm.set_touch_policy(policy);
+		for &(x,y,w,h) in [ (60.0, 95.0, 100.0, 20.0), (80.0, 150.0, 100.0, 30.0), (120.0, 40.0, 79.0, 10.0), (90.0, 5.0, 20.0, 10.0), (1.0, 60.0, 150.0, 60.0) ].iter() {
+			let impact = spm.would_block(x,y,w,h);
+			let mut copy = spm.clone();
+			copy.insert_obstacle(x,y,w,h);
+			assert_eq!(impact.cost_after, copy.graph[copy.start_point_index].cost, "{:?} with {:?}", (x,y,w,h), policy);
+		}
+	}
+}
+
+#[test]
+fn regions_split_and_join() {
+	let start = (0.0, 0.0);
+	let end = (0.0, 300.0);
+	let map = (-100.0, 0.0, 200.0, 300.0);
+	let mut spm = JkmShortestPathMap::new(start, end, map);
+	spm.add_map_border();
+	assert!(spm.is_reachable(start.0, start.1));
+	assert!(spm.is_reachable(50.0, 50.0));
+	
+	let array_of_obstacles = [
+		(-110.0, 150.0, 70.0, 50.0),
+		(-60.0, 145.0, 70.0, 50.0),
+		(0.0, 150.0, 70.0, 50.0),
+		(50.0, 130.0, 70.0, 50.0),
+	];
+	for &(x,y,w,h) in array_of_obstacles.iter() {
+		spm.insert_obstacle(x,y,w,h);
+	}
+	check_update_invariants(&spm);
+	
+	assert_eq!(spm.region_id(end.0, end.1), Some(DESTINATION_REGION));
+	assert!(spm.is_reachable(-50.0, 250.0));
+	assert!(!spm.is_reachable(start.0, start.1));
+	assert!(!spm.is_reachable(50.0, 50.0));
+	let cut_off = spm.region_id(start.0, start.1);
+	assert!(cut_off.is_some() && cut_off != Some(DESTINATION_REGION));
+	assert_eq!(spm.region_id(50.0, 50.0), cut_off);
+	assert_eq!(spm.region_id(5.0, 160.0), None, "Blocked coordinates have no region.");
+	assert_eq!(spm.region_id(150.0, 50.0), None, "Coordinates outside of the map have no region.");
+	
+	let (x, y, w, h) = spm.reachable_area();
+	assert!(y >= 145.0 && y + h == 300.0 && x == -100.0 && x + w == 100.0, "Unexpected reachable area: ({}, {}, {}, {})", x, y, w, h);
+	
+	spm.remove_obstacle(0.0, 150.0, 70.0, 50.0);
+	check_update_invariants(&spm);
+	assert!(spm.is_reachable(start.0, start.1));
+	assert!(spm.is_reachable(50.0, 50.0));
+	assert_eq!(spm.reachable_area(), (map.0, map.1, map.2, map.3));
+	
+	// parts that are cut off are split and joined again while the labels of the other nodes stay as they are
+	for &(x,y,w,h) in [ (-110.0, 60.0, 220.0, 10.0), (-20.0, -10.0, 10.0, 75.0), (-20.0, 20.0, 130.0, 10.0), (0.0, 150.0, 70.0, 50.0) ].iter() {
+		spm.insert_obstacle(x,y,w,h);
+		check_update_invariants(&spm);
+	}
+	assert!(!spm.is_reachable(start.0, start.1));
+	assert!(spm.region_id(-50.0, 100.0) != spm.region_id(start.0, start.1));
+	assert!(spm.region_id(-50.0, 10.0) != spm.region_id(start.0, start.1));
+	spm.remove_obstacle(-20.0, -10.0, 10.0, 75.0);
+	check_update_invariants(&spm);
+	assert_eq!(spm.region_id(-50.0, 10.0), spm.region_id(start.0, start.1));
+	assert!(spm.region_id(-50.0, 100.0) != spm.region_id(start.0, start.1));
+}
+
+#[test]
+fn scheduled_gate() {
+	let start = (100.0, 0.0);
+	let end = (100.0, 300.0);
+	let map = (0.0,0.0,200.0,300.0);
+	let mut spm = JkmShortestPathMap::new(start, end, map);
+	spm.add_map_border();
+	spm.insert_obstacle(-1.0, 140.0, 91.0, 20.0);
+	spm.insert_obstacle(110.0, 140.0, 91.0, 20.0);
+	let gate = spm.add_scheduled_obstacle(85.0, 140.0, 30.0, 20.0, Schedule::Periodic { period: 10.0, active_for: 6.0, offset: 0.0 });
+	check_update_invariants(&spm);
+	assert!(!spm.is_reachable(start.0, start.1));
+	assert_eq!(spm.next_scheduled_change(), Some(6.0));
+	let layout = graph_layout(&spm);
+	let (_, token) = spm.next_checkpoint_with_token(50.0, 250.0).unwrap();
+	assert_eq!(spm.next_opening(start.0, start.1), Some(6.0));
+	assert_eq!(spm.next_opening(end.0, end.1), Some(0.0));
+	// the map is left as it was
+	check_update_invariants(&spm);
+	assert_eq!(spm.time(), 0.0);
+	assert_eq!(graph_layout(&spm), layout);
+	assert!(spm.is_still_valid(&token));
+	assert!(!spm.is_reachable(start.0, start.1));
+	
+	spm.advance_time(6.0);
+	check_update_invariants(&spm);
+	assert!(spm.is_reachable(start.0, start.1));
+	assert_eq!(spm.graph[spm.start_point_index].cost, 300.0);
+	
+	spm.advance_time(12.0);
+	check_update_invariants(&spm);
+	assert!(!spm.is_reachable(start.0, start.1));
+	assert_eq!(spm.next_scheduled_change(), Some(16.0));
+	
+	assert!(spm.remove_scheduled_obstacle(gate));
+	assert!(!spm.remove_scheduled_obstacle(gate));
+	check_update_invariants(&spm);
+	assert!(spm.is_reachable(start.0, start.1));
+	
+	// a second gate that only closes between 30 and 40
+	spm.advance_time(20.0);
+	spm.add_scheduled_obstacle(80.0, 150.0, 40.0, 5.0, Schedule::Window { from: 30.0, until: 40.0 });
+	assert!(spm.is_reachable(start.0, start.1));
+	spm.advance_time(33.0);
+	check_update_invariants(&spm);
+	assert!(!spm.is_reachable(start.0, start.1));
+	assert_eq!(spm.next_opening(start.0, start.1), Some(40.0));
+	spm.advance_time(40.0);
+	check_update_invariants(&spm);
+	assert!(spm.is_reachable(start.0, start.1));
+	assert_eq!(spm.next_scheduled_change(), None);
+	
+	// several obstacles that change at the same time are applied together
+	spm.add_scheduled_obstacle(80.0, 150.0, 40.0, 5.0, Schedule::Window { from: 50.0, until: 60.0 });
+	spm.add_scheduled_obstacle(20.0, 60.0, 40.0, 40.0, Schedule::Window { from: 50.0, until: 60.0 });
+	spm.advance_time(55.0);
+	check_update_invariants(&spm);
+	assert!(!spm.is_reachable(start.0, start.1));
+	assert_eq!(spm.region_id(start.0, start.1), spm.region_id(50.0, 50.0));
+	spm.advance_time(60.0);
+	check_update_invariants(&spm);
+	assert!(spm.is_reachable(start.0, start.1));
+	assert!(spm.is_reachable(50.0, 50.0));
+}
+
+
+// Positions, edges and costs of all nodes
+fn graph_layout(spm: &JkmShortestPathMap) -> Vec<(f64, f64, [Option<usize>;4], f64)> {
+	spm.graph.iter().map(|n| (n.x, n.y, n.neighbours, n.cost)).collect()
+}
+
+#[test]
+fn toggled_door_keeps_its_nodes() {
+	let start = (100.0, 0.0);
+	let end = (100.0, 300.0);
+	let map = (0.0,0.0,200.0,300.0);
+	let mut spm = JkmShortestPathMap::new(start, end, map);
+	spm.add_map_border();
+	spm.insert_obstacle(-1.0, 140.0, 91.0, 20.0);
+	spm.insert_obstacle(110.0, 140.0, 91.0, 20.0);
+	spm.insert_obstacle(40.0, 60.0, 120.0, 20.0);
+	let door = spm.insert_toggleable_obstacle(85.0, 140.0, 30.0, 20.0, true);
+	check_update_invariants(&spm);
+	assert_eq!(spm.obstacle_is_enabled(door), Some(true));
+	assert!(!spm.is_reachable(start.0, start.1));
+	
+	assert!(spm.set_obstacle_enabled(door, false));
+	check_update_invariants(&spm);
+	assert!(spm.is_reachable(start.0, start.1));
+	let open_cost = spm.graph[spm.start_point_index].cost;
+	let open_state = graph_layout(&spm);
+	
+	assert!(spm.set_obstacle_enabled(door, true));
+	check_update_invariants(&spm);
+	assert!(!spm.is_reachable(start.0, start.1));
+	let closed_state = graph_layout(&spm);
+	assert_eq!(closed_state.len(), open_state.len());
+	
+	// shortest paths with equal costs can be chosen differently, everything else has to stay the same
+	for _ in 0..3 {
+		spm.set_obstacle_enabled(door, false);
+		check_update_invariants(&spm);
+		assert!(graph_layout(&spm) == open_state);
+		spm.set_obstacle_enabled(door, true);
+		check_update_invariants(&spm);
+		assert!(graph_layout(&spm) == closed_state);
+	}
+	
+	// the paths have to be the same as without toggling
+	spm.set_obstacle_enabled(door, false);
+	let mut fresh = JkmShortestPathMap::new(start, end, map);
+	fresh.add_map_border();
+	fresh.insert_obstacle(-1.0, 140.0, 91.0, 20.0);
+	fresh.insert_obstacle(110.0, 140.0, 91.0, 20.0);
+	fresh.insert_obstacle(40.0, 60.0, 120.0, 20.0);
+	assert_eq!(open_cost, fresh.graph[fresh.start_point_index].cost);
+	for node in fresh.graph.iter() {
+		if let Some(n) = query::node_at(&spm, node.x, node.y) {
+			assert_eq!(spm.graph[n].cost, node.cost);
+		}
+	}
+	
+	assert!(spm.remove_scheduled_obstacle(door));
+	assert!(!spm.set_obstacle_enabled(door, true));
+	assert_eq!(spm.obstacle_is_enabled(door), None);
+	
+	// several doors switched in turns while another obstacle comes and goes, which moves their nodes around in the graph
+	let walls = [(-1.0, 140.0, 91.0, 20.0), (110.0, 140.0, 91.0, 20.0), (40.0, 60.0, 120.0, 20.0)];
+	let door_areas = [(85.0, 140.0, 30.0, 20.0), (50.0, 90.0, 30.0, 30.0), (150.0, 200.0, 30.0, 30.0)];
+	let block = (20.0, 220.0, 10.0, 10.0);
+	let doors: Vec<_> = door_areas.iter().map(|&(x,y,w,h)| spm.insert_toggleable_obstacle(x,y,w,h, true)).collect();
+	let mut seed = 7u32;
+	for step in 0..30 {
+		seed = seed.wrapping_mul(1103515245).wrapping_add(12345);
+		let k = (seed >> 16) as usize % doors.len();
+		let enabled = spm.obstacle_is_enabled(doors[k]) == Some(false);
+		spm.set_obstacle_enabled(doors[k], enabled);
+		if step % 7 == 3 { spm.insert_obstacle(block.0, block.1, block.2, block.3); }
+		if step % 7 == 5 { spm.remove_obstacle(block.0, block.1, block.2, block.3); }
+		check_update_invariants(&spm);
+		
+		let mut fresh = JkmShortestPathMap::new(start, end, map);
+		fresh.add_map_border();
+		for &(x,y,w,h) in walls.iter().chain(door_areas.iter().zip(doors.iter()).filter(|&(_, &id)| spm.obstacle_is_enabled(id) == Some(true)).map(|(area, _)| area)) {
+			fresh.insert_obstacle(x,y,w,h);
+		}
+		if step % 7 >= 3 && step % 7 < 5 { fresh.insert_obstacle(block.0, block.1, block.2, block.3); }
+		for node in fresh.graph.iter() {
+			if let Some(n) = query::node_at(&spm, node.x, node.y) {
+				let cost = spm.graph[n].cost;
+				assert!(cost == node.cost || (cost - node.cost).abs() < 1e-9, "Step {}: cost {} at ({}, {}) instead of {}", step, cost, node.x, node.y, node.cost);
+			}
+		}
+	}
+	
+	// removing an enabled door links the nodes that were kept inside of it again
+	let start = (1.0, 1.0);
+	let end = (99.0, 99.0);
+	let mut spm = JkmShortestPathMap::new(start, end, (0.0,0.0,100.0,100.0));
+	let door = spm.insert_toggleable_obstacle(40.0, 40.0, 20.0, 20.0, true);
+	spm.set_obstacle_enabled(door, false);
+	spm.insert_obstacle(45.0, 45.0, 5.0, 5.0);
+	spm.insert_obstacle(52.0, 30.0, 3.0, 50.0);
+	spm.remove_obstacle(45.0, 45.0, 5.0, 5.0);
+	spm.set_obstacle_enabled(door, true);
+	check_update_invariants(&spm);
+	assert!(spm.remove_scheduled_obstacle(door));
+	check_update_invariants(&spm);
+	assert!(spm.is_reachable(45.0, 45.0));
+	assert_eq!(spm.region_id(45.0, 45.0), spm.region_id(start.0, start.1));
+	let mut fresh = JkmShortestPathMap::new(start, end, (0.0,0.0,100.0,100.0));
+	fresh.insert_obstacle(52.0, 30.0, 3.0, 50.0);
+	for node in fresh.graph.iter() {
+		if let Some(n) = query::node_at(&spm, node.x, node.y) {
+			assert_eq!(spm.graph[n].cost, node.cost);
+		}
+	}
+}
+
+// Costs of all nodes
+fn costs(spm: &JkmShortestPathMap) -> Vec<f64> {
+	spm.graph.iter().map(|n| n.cost).collect()
+}
+
+// Costs of all nodes after computing the shortest paths from scratch
+fn costs_from_scratch(spm: &JkmShortestPathMap) -> Vec<f64> {
+	let mut copy = spm.clone();
+	copy.recompute_paths();
+	costs(&copy)
+}
+
+#[test]
+fn one_way_zone() {
+	let start = (100.0, 0.0);
+	let end = (100.0, 300.0);
+	let map = (0.0,0.0,200.0,300.0);
+	let mut spm = JkmShortestPathMap::new(start, end, map);
+	let token = spm.checkpoint();
+	
+	// paths may only go north inside of the zone, the path from the start has to go around it on the west side
+	spm.insert_directional_zone(50.0, 100.0, 200.0, 100.0, Direction::North);
+	check_update_invariants(&spm);
+	assert_eq!(spm.graph[spm.start_point_index].cost, 500.0);
+	assert_eq!(spm.next_checkpoint(start.0, start.1), Some((0.0, 0.0)));
+	// leaving the zone sideways is allowed
+	assert_eq!(spm.nearest_checkpoint(100.0, 150.0), Some((0.0, 150.0)));
+	assert!(spm.is_reachable(100.0, 150.0));
+	
+	// new edges that cross the zone are split at its border
+	spm.insert_obstacle(140.0, 20.0, 20.0, 20.0);
+	check_update_invariants(&spm);
+	assert_eq!(spm.graph[spm.start_point_index].cost, 500.0);
+	// only the paths into the zone are rerouted, they have to end up like computed from scratch
+	spm.insert_obstacle(20.0, 150.0, 60.0, 20.0);
+	assert_eq!(costs(&spm), costs_from_scratch(&spm));
+	
+	// a zone in the opposite direction cuts off the start point completely
+	spm.insert_directional_zone(-10.0, 240.0, 220.0, 20.0, Direction::North);
+	check_update_invariants(&spm);
+	assert_eq!(costs(&spm), costs_from_scratch(&spm));
+	assert!(!spm.is_reachable(start.0, start.1));
+	assert_eq!(spm.next_checkpoint(start.0, start.1), None);
+	
+	assert!(spm.rollback(token));
+	spm.insert_obstacle(140.0, 20.0, 20.0, 20.0);
+	check_update_invariants(&spm);
+	assert_eq!(spm.graph[spm.start_point_index].cost, 300.0);
+}
+
+#[test]
+fn portal_jumps_over_wall() {
+	let start = (100.0, 0.0);
+	let end = (100.0, 300.0);
+	let map = (0.0,0.0,200.0,300.0);
+	let mut spm = JkmShortestPathMap::new(start, end, map);
+	spm.add_map_border();
+	spm.insert_obstacle(-1.0, 140.0, 202.0, 20.0);
+	assert!(!spm.is_reachable(start.0, start.1));
+	
+	// a portal that only leads back does not help
+	let mut copy = spm.clone();
+	copy.add_portal((50.0, 200.0), (50.0, 100.0), 10.0, false);
+	check_update_invariants(&copy);
+	assert!(!copy.is_reachable(start.0, start.1));
+	copy.add_portal((150.0, 100.0), (150.0, 200.0), 10.0, true);
+	check_update_invariants(&copy);
+	assert!(copy.is_reachable(start.0, start.1));
+	
+	spm.add_portal((50.0, 100.0), (50.0, 200.0), 10.0, false);
+	check_update_invariants(&spm);
+	assert_eq!(spm.graph[spm.start_point_index].cost, 310.0);
+	assert_eq!(spm.next_step(start.0, start.1), Some(Step::Walk(50.0, 0.0)));
+	assert_eq!(spm.next_step(50.0, 0.0), Some(Step::Walk(50.0, 100.0)));
+	assert_eq!(spm.next_step(50.0, 100.0), Some(Step::Jump(50.0, 200.0)));
+	assert_eq!(spm.next_checkpoint(50.0, 100.0), Some((50.0, 200.0)));
+	assert_eq!(spm.snapshot().next_step(50.0, 100.0), Some(Step::Jump(50.0, 200.0)));
+	
+	// the portal can not be used while one of its ends is covered
+	spm.insert_obstacle(40.0, 90.0, 20.0, 20.0);
+	check_update_invariants(&spm);
+	assert!(!spm.is_reachable(start.0, start.1));
+	spm.remove_obstacle(40.0, 90.0, 20.0, 20.0);
+	check_update_invariants(&spm);
+	assert_eq!(spm.graph[spm.start_point_index].cost, 310.0);
+	assert_eq!(spm.next_step(50.0, 100.0), Some(Step::Jump(50.0, 200.0)));
+	
+	// erased nodes are replaced by the last ones in the graph, which can be the ends of the portal
+	for &(x, y, w, h) in [(20.0, 20.0, 40.0, 40.0), (120.0, 220.0, 40.0, 40.0), (20.0, 220.0, 20.0, 20.0)].iter() {
+		spm.insert_obstacle(x, y, w, h);
+		check_update_invariants(&spm);
+		spm.remove_obstacle(x, y, w, h);
+		check_update_invariants(&spm);
+	}
+	assert_eq!(spm.graph[spm.start_point_index].cost, 310.0);
+	
+	// the stored ends follow a portal end that takes the place of an erased node
+	let mut moved = spm.clone();
+	let end = (0..moved.graph.len()).filter(|&n| moved.graph[n].portal.is_some()).max().unwrap();
+	let special = [moved.start_point_index, moved.end_point_index];
+	for n in end + 1..moved.graph.len() {
+		if !special.contains(&n) { moved.erase_node(n); }
+	}
+	let erased = (0..end).find(|&n| !special.contains(&n) && moved.graph[n].portal.is_none()).unwrap();
+	moved.erase_node(erased);
+	moved.swap_out_dead_nodes();
+	assert!(moved.graph[erased].portal.is_some());
+	inv_portal_ends_are_stored(&moved);
+}
+
+#[test]
+fn stairs_between_layers() {
+	let floors = [(0.0, 0.0, 100.0, 100.0), (0.0, 0.0, 100.0, 100.0), (0.0, 20.0, 50.0, 50.0)];
+	let mut spm = JkmShortestPathMap::new_layered((0, 50.0, 10.0), (1, 50.0, 90.0), &floors);
+	check_module_invariants(&spm);
+	assert_eq!(spm.layer_count(), 3);
+	assert_eq!(spm.layer_position(spm.get_destination_coordinates().0, 90.0), Some((1, 50.0, 90.0)));
+	let start = spm.map_position(0, 50.0, 10.0);
+	assert!(!spm.is_reachable(start.0, start.1));
+	
+	spm.add_layer_connection(0, (90.0, 50.0), 1, (10.0, 50.0), 5.0);
+	check_module_invariants(&spm);
+	assert!(spm.is_reachable(start.0, start.1));
+	assert_eq!(spm.graph[spm.start_point_index].cost, 165.0);
+	assert_eq!(spm.next_checkpoint_on(0, 90.0, 50.0), Some((1, 10.0, 50.0)));
+	
+	// a wall on the lower floor makes the way to the stairs longer, the upper floor is not affected
+	spm.insert_obstacle_on(0, 60.0, -10.0, 10.0, 80.0);
+	check_module_invariants(&spm);
+	assert_eq!(spm.graph[spm.start_point_index].cost, 205.0);
+	let upper_stairs = spm.map_position(1, 10.0, 50.0);
+	assert!(!spm.coordinate_is_blocked(upper_stairs.0, 20.0));
+	spm.remove_obstacle_on(0, 60.0, -10.0, 10.0, 80.0);
+	check_module_invariants(&spm);
+	assert_eq!(spm.graph[spm.start_point_index].cost, 165.0);
+	
+	// the third floor is only reachable over stairs
+	let attic = spm.map_position(2, 25.0, 40.0);
+	assert!(!spm.is_reachable(attic.0, attic.1));
+	assert!(spm.coordinate_is_blocked(attic.0, 10.0));
+	spm.add_layer_connection(2, (25.0, 30.0), 1, (50.0, 50.0), 10.0);
+	check_module_invariants(&spm);
+	assert!(spm.is_reachable(attic.0, attic.1));
+	assert_eq!(spm.next_checkpoint_on(2, 25.0, 30.0), Some((1, 50.0, 50.0)));
+	
+	// the space between the layers can neither be removed nor opened by inserting the obstacles again
+	let blocker = spm.layer_blockers[0];
+	spm.remove_obstacle(blocker.0, blocker.1, blocker.2, blocker.3);
+	assert!(spm.obstacles.contains(&blocker));
+	spm.set_touch_policy(TouchPolicy::Closed);
+	spm.set_touch_policy(TouchPolicy::Open);
+	check_update_invariants(&spm);
+	assert_eq!(spm.graph[spm.start_point_index].cost, 165.0);
+	assert!(spm.coordinate_is_blocked(attic.0, 10.0));
+	assert!(spm.coordinate_is_blocked(blocker.0 + blocker.2 / 2.0, 50.0));
+}
+
+#[test]
+fn chunked_world_keeps_costs_of_unloaded_chunks() {
+	let mut world = ChunkedWorld::new(100.0, 2);
+	assert_eq!(world.chunk_at(150.0, -20.0), (1, -1));
+	for x in 0..3 {
+		for y in -1..2 { world.load_chunk((x, y), &[]); }
+	}
+	assert_eq!(world.path_cost((10.0, 25.0), (290.0, 25.0)), Some(280.0));
+	assert_eq!(world.path_cost((10.0, 25.0), (60.0, 80.0)), Some(105.0));
+	
+	// a wall through the middle column, only open at the bottom of row 0
+	world.insert_obstacle(140.0, -100.0, 20.0, 190.0);
+	assert_eq!(world.path_cost((10.0, 25.0), (290.0, 25.0)), Some(410.0));
+	assert_eq!(world.path_cost((120.0, 25.0), (180.0, 25.0)), Some(190.0));
+	assert_eq!(world.unload_chunk((1, 0)), vec![(140.0, -100.0, 20.0, 190.0)]);
+	assert!(!world.is_loaded((1, 0)));
+	assert_eq!(world.path_cost((10.0, 25.0), (290.0, 25.0)), Some(410.0));
+	
+	// closing the gap, the path has to go through row 1
+	world.load_chunk((1, 0), &[(140.0, -50.0, 20.0, 200.0)]);
+	assert_eq!(world.path_cost((10.0, 25.0), (290.0, 25.0)), Some(430.0));
+	let path = world.path((10.0, 25.0), (290.0, 25.0)).unwrap();
+	assert_eq!((path.cost, path.points[0], path.points[path.points.len() - 1]), (430.0, (10.0, 25.0), (290.0, 25.0)));
+	assert!(path.points.windows(2).all(|pair| pair[0].0 == pair[1].0 || pair[0].1 == pair[1].1));
+	let length: f64 = path.points.windows(2).map(|pair| (pair[0].0 - pair[1].0).abs() + (pair[0].1 - pair[1].1).abs()).sum();
+	assert_eq!(length, 430.0);
+	world.insert_obstacle(140.0, 50.0, 20.0, 100.0);
+	assert_eq!(world.path_cost((10.0, 25.0), (290.0, 25.0)), Some(530.0));
+	world.remove_obstacle(140.0, 50.0, 20.0, 100.0);
+	assert_eq!(world.path_cost((10.0, 25.0), (290.0, 25.0)), Some(430.0));
+}
+
+#[test]
+fn chunked_world_edits_match_reload() {
+	// removing obstacles that stick out of their chunks has to leave the same costs as loading the chunks without them
+	let mut seed = 7u32;
+	let mut random = |range: f64| { seed = seed.wrapping_mul(1103515245).wrapping_add(12345); ((seed >> 16) % 32768) as f64 / 32768.0 * range };
+	let obstacles: Vec<(f64, f64, f64, f64)> = (0..12).map(|_| (random(500.0) - 100.0, random(500.0) - 100.0, random(80.0) + 5.0, random(80.0) + 5.0)).collect();
+	let points: Vec<(f64, f64)> = (0..6).map(|_| (random(500.0) - 100.0, random(500.0) - 100.0)).collect();
+	let mut world = ChunkedWorld::new(100.0, 4);
+	for x in -1..5 {
+		for y in -1..5 { world.load_chunk((x, y), &[]); }
+	}
+	for &(x,y,w,h) in obstacles.iter() { world.insert_obstacle(x,y,w,h); }
+	for &(x,y,w,h) in obstacles[..4].iter() { world.remove_obstacle(x,y,w,h); }
+	let mut reloaded = ChunkedWorld::new(100.0, 4);
+	for x in -1..5 {
+		for y in -1..5 { reloaded.load_chunk((x, y), &obstacles[4..]); }
+	}
+	for pair in points.windows(2) {
+		assert_eq!(world.path_cost(pair[0], pair[1]), reloaded.path_cost(pair[0], pair[1]), "From {:?} to {:?}", pair[0], pair[1]);
+	}
+}
+
+#[test]
+fn hierarchical_path_follows_obstacles() {
+	let mut spm = JkmShortestPathMap::new((10.0, 10.0), (290.0, 90.0), (0.0, 0.0, 300.0, 100.0));
+	spm.enable_hierarchy(100.0, 4);
+	let (from, to) = ((10.0, 37.5), (290.0, 37.5));
+	assert_eq!(spm.hierarchical_path(from, to).map(|p| p.cost), Some(280.0));
+	
+	spm.insert_obstacle(140.0, -10.0, 20.0, 80.0);
+	let path = spm.hierarchical_path(from, to).unwrap();
+	assert_eq!(path.cost, 345.0);
+	assert_eq!((path.points[0], path.points[path.points.len() - 1]), (from, to));
+	let mut length = 0.0;
+	for pair in path.points.windows(2) {
+		let ((x0, y0), (x1, y1)) = (pair[0], pair[1]);
+		assert!(x0 == x1 || y0 == y1);
+		assert!(!spm.coordinate_is_blocked((x0 + x1) / 2.0, (y0 + y1) / 2.0));
+		length += (x1 - x0).abs() + (y1 - y0).abs();
+	}
+	assert_eq!(length, path.cost);
+	
+	let token = spm.checkpoint();
+	spm.insert_obstacle(140.0, 60.0, 20.0, 50.0);
+	assert_eq!(spm.hierarchical_path(from, to), None);
+	spm.rollback(token);
+	assert_eq!(spm.hierarchical_path(from, to).map(|p| p.cost), Some(345.0));
+	spm.remove_obstacle(140.0, -10.0, 20.0, 80.0);
+	assert_eq!(spm.hierarchical_path(from, to).map(|p| p.cost), Some(280.0));
+	
+	// the clusters around the obstacle are computed again, the costs are the same as with new clusters
+	spm.insert_obstacle(230.0, 20.0, 20.0, 20.0);
+	let hierarchy = spm.hierarchy.as_ref().unwrap();
+	assert!(hierarchy.changed.is_empty());
+	let updated = hierarchy.clusters.clone();
+	spm.enable_hierarchy(100.0, 4);
+	assert_eq!(spm.hierarchy.as_ref().unwrap().clusters, updated);
+	
+	// the map can grow, the new clusters are added
+	spm.resize_map(0.0, 0.0, 400.0, 100.0).unwrap();
+	assert_eq!(spm.hierarchical_path(from, (390.0, 37.5)).map(|p| p.cost), Some(385.0));
+	let updated = spm.hierarchy.as_ref().unwrap().clusters.clone();
+	spm.enable_hierarchy(100.0, 4);
+	assert_eq!(spm.hierarchy.as_ref().unwrap().clusters, updated);
+	
+	spm.disable_hierarchy();
+	assert_eq!(spm.hierarchical_path(from, to), spm.find_path(from, to));
+}
+
+#[test]
+fn find_path_between_arbitrary_points() {
+	let mut spm = JkmShortestPathMap::new((10.0, 10.0), (290.0, 90.0), (0.0, 0.0, 300.0, 100.0));
+	spm.add_map_border();
+	spm.insert_obstacle(140.0, -10.0, 20.0, 80.0);
+	let layout = graph_layout(&spm);
+	
+	let path = spm.find_path((10.0, 10.0), (290.0, 90.0)).unwrap();
+	assert_eq!(path.cost, spm.graph[spm.start_point_index].cost);
+	let path = spm.find_path((10.0, 37.5), (290.0, 37.5)).unwrap();
+	assert_eq!(path.cost, 345.0);
+	assert_eq!((path.points[0], path.points[path.points.len() - 1]), ((10.0, 37.5), (290.0, 37.5)));
+	assert!(path.points.windows(2).all(|pair| pair[0].0 == pair[1].0 || pair[0].1 == pair[1].1));
+	assert_eq!(spm.find_path((150.0, 20.0), (290.0, 37.5)), None);
+	assert_eq!(spm.find_path((150.0, 80.0), (150.0, 80.0)).map(|p| p.cost), Some(0.0));
+	assert_eq!(graph_layout(&spm), layout);
+	
+	// the same costs as the paths of all nodes to the destination of the map, in both directions
+	spm.insert_obstacle(40.0, 30.0, 60.0, 20.0);
+	spm.insert_obstacle(200.0, 20.0, 30.0, 60.0);
+	let destination = spm.get_destination_coordinates();
+	for node in spm.graph.iter() {
+		let a = (node.x, node.y);
+		if spm.coordinate_is_blocked(a.0, a.1) || a.0 < 0.0 || a.0 > 300.0 || a.1 < 0.0 || a.1 > 100.0 { continue; }
+		assert_eq!(spm.find_path(a, destination).map_or(f64::INFINITY, |p| p.cost), node.cost, "from {:?}", a);
+		assert_eq!(spm.find_path(destination, a).map_or(f64::INFINITY, |p| p.cost), node.cost, "to {:?}", a);
+	}
+	spm.remove_obstacle(40.0, 30.0, 60.0, 20.0);
+	spm.remove_obstacle(200.0, 20.0, 30.0, 60.0);
+	
+	// a portal is used even though it leads away from the target at first
+	spm.add_portal((130.0, 10.0), (170.0, 10.0), 1.0, false);
+	assert_eq!(spm.find_path((100.0, 10.0), (200.0, 10.0)).map(|p| p.cost), Some(30.0 + 1.0 + 30.0));
+	assert_eq!(spm.find_path((200.0, 10.0), (100.0, 10.0)).map(|p| p.cost), Some(100.0 + 2.0 * 60.0));
+}
+
+#[test]
+fn resize_map_opens_new_ways() {
+	let mut spm = JkmShortestPathMap::new((10.0, 20.0), (190.0, 80.0), (0.0, 0.0, 200.0, 100.0));
+	spm.add_map_border();
+	spm.insert_obstacle(100.0, -10.0, 20.0, 80.0);
+	spm.insert_obstacle(100.0, 60.0, 20.0, 50.0);
+	check_module_invariants(&spm);
+	assert!(!spm.is_reachable(10.0, 20.0));
+	let token = spm.checkpoint();
+	
+	spm.resize_map(0.0, -50.0, 300.0, 150.0).unwrap();
+	check_module_invariants(&spm);
+	assert_eq!(spm.graph[spm.start_point_index].cost, 300.0);
+	assert!(spm.is_reachable(250.0, -40.0));
+	assert!(spm.obstacles.contains(&(300.0, -50.0, 0.0625, 150.0)));
+	assert!(!spm.obstacles.contains(&(200.0, 0.0, 0.0625, 100.0)));
+	// the lower part of the wall still reaches the border and is cut there
+	assert!(!spm.is_reachable(110.0, 99.0));
+	
+	spm.rollback(token);
+	check_module_invariants(&spm);
+	assert!(!spm.is_reachable(10.0, 20.0));
+	assert!(!spm.is_reachable(250.0, 50.0));
+	
+	assert!(spm.resize_map(10.0, 0.0, 300.0, 100.0).is_err());
+	assert!(JkmShortestPathMap::new_layered((0, 10.0, 10.0), (1, 50.0, 50.0), &[(0.0, 0.0, 100.0, 100.0); 2]).resize_map(0.0, 0.0, 200.0, 200.0).is_err());
+}
+
+#[test]
+fn resize_map_cuts_obstacles_beyond_the_old_border() {
+	let mut spm = JkmShortestPathMap::new((10.0, 50.0), (190.0, 50.0), (0.0, 0.0, 200.0, 100.0));
+	spm.insert_obstacle(150.0, 80.0, 100.0, 40.0);
+	spm.insert_obstacle(250.0, 20.0, 30.0, 30.0);
+	spm.insert_obstacle(50.0, -20.0, 20.0, 90.0);
+	spm.resize_map(-50.0, -50.0, 400.0, 250.0).unwrap();
+	check_module_invariants(&spm);
+	
+	let mut fresh = JkmShortestPathMap::new((10.0, 50.0), (190.0, 50.0), (-50.0, -50.0, 400.0, 250.0));
+	for &(x, y, w, h) in spm.obstacles.clone().iter() {
+		fresh.insert_obstacle(x, y, w, h);
+	}
+	assert_eq!(spm.graph[spm.start_point_index].cost, fresh.graph[fresh.start_point_index].cost);
+	for &(x, y) in [(10.0, 50.0), (260.0, 10.0), (200.0, 150.0), (240.0, 70.0), (-40.0, -40.0)].iter() {
+		assert_eq!(spm.find_path((x, y), (190.0, 50.0)).map(|p| p.cost), fresh.find_path((x, y), (190.0, 50.0)).map(|p| p.cost));
+	}
+}
+
+#[test]
+fn set_border_closes_and_opens_sides() {
+	let mut spm = JkmShortestPathMap::new((10.0, 10.0), (190.0, 20.0), (0.0, 0.0, 200.0, 100.0));
+	spm.insert_obstacle(100.0, -10.0, 20.0, 105.0);
+	check_module_invariants(&spm);
+	assert_eq!(spm.graph[spm.start_point_index].cost, 230.0);
+	
+	spm.set_border(BorderMode::Closed(0.5), BorderMode::Open, BorderMode::Open, BorderMode::Open);
+	check_module_invariants(&spm);
+	assert_eq!(spm.graph[spm.start_point_index].cost, 340.0);
+	assert!(spm.obstacles.contains(&(0.0, -0.5, 200.0, 0.5)));
+	
+	spm.set_border(BorderMode::Open, BorderMode::Open, BorderMode::Open, BorderMode::Open);
+	check_module_invariants(&spm);
+	assert_eq!(spm.graph[spm.start_point_index].cost, 230.0);
+	
+	spm.add_map_border();
+	check_module_invariants(&spm);
+	assert_eq!(spm.graph[spm.start_point_index].cost, 340.0);
+	assert_eq!(spm.border(), [BorderMode::Closed(DEFAULT_BORDER_THICKNESS); 4]);
+}
+
+#[test]
+fn integer_and_f32_maps_match_f64() {
+	let obstacles = [(30, -1, 40, 301), (100, 50, 50, 301), (200, -1, 50, 51), (200, 49, 50, 51), (200, 99, 50, 151)];
+	let mut exact = JkmShortestPathMap::new((0.0, 200.0), (300.0, 200.0), (0.0, 0.0, 300.0, 350.0));
+	let mut integer = JkmShortestPathMap::<i32>::new((0, 200), (300, 200), (0, 0, 300, 350));
+	let mut single = JkmShortestPathMap::<f32>::new((0.0, 200.0), (300.0, 200.0), (0.0, 0.0, 300.0, 350.0));
+	exact.add_map_border();
+	integer.add_map_border();
+	single.add_map_border();
+	for &(x, y, w, h) in obstacles.iter() {
+		exact.insert_obstacle(x as f64, y as f64, w as f64, h as f64);
+		integer.insert_obstacle(x, y, w, h);
+		single.insert_obstacle(x as f32, y as f32, w as f32, h as f32);
+		check_module_invariants(&integer);
+		check_module_invariants(&single);
+		assert_eq!(integer.graph[integer.start_point_index].cost as f64, exact.graph[exact.start_point_index].cost);
+		assert_eq!(single.graph[single.start_point_index].cost as f64, exact.graph[exact.start_point_index].cost);
+	}
+	assert_eq!(integer.graph[integer.start_point_index].cost, 900);
+	assert_eq!(integer.next_checkpoint(0, 200), Some((30, 200)));
+	assert_eq!(exact.next_checkpoint(0.0, 200.0), Some((30.0, 200.0)));
+	assert_eq!(integer.next_checkpoint(30, 200), Some((30, 300)));
+}
+
+#[test]
+fn integer_tiles_block_exactly() {
+	let cells = [
+		false, true,  true,  false,
+		false, true,  true,  false,
+		false, false, true,  true,
+		true,  true,  false, false,
+	];
+	let rectangles = tile_rectangles(&cells, 4);
+	assert_eq!(rectangles, vec![(1, 0, 2, 2), (2, 2, 2, 1), (0, 3, 2, 1)]);
+	
+	let mut spm = JkmShortestPathMap::<i64>::new((0, 50), (200, 50), (0, 0, 200, 200));
+	spm.add_map_border();
+	spm.insert_obstacle(100, -1, 10, 101);
+	spm.insert_obstacle(100, 100, 10, 101);
+	check_module_invariants(&spm);
+	// touching obstacles leave a gap
+	assert_eq!(spm.graph[spm.start_point_index].cost, 300);
+	spm.insert_obstacle(100, 99, 10, 2);
+	check_module_invariants(&spm);
+	assert!(!spm.is_reachable(0, 50));
+	assert_eq!(spm.nearest_checkpoint(150, 51), Some((200, 51)));
+}
+
+#[test]
+fn occupancy_grid_blocks_diagonal_gaps() {
+	let mut cells = [
+		false, false, false, true,
+		false, false, true,  false,
+		false, true,  false, false,
+		true,  false, false, false,
+	];
+	let spm = JkmShortestPathMap::from_occupancy_grid(&cells, 4, 10.0, (5.0, 5.0), (35.0, 35.0));
+	check_module_invariants(&spm);
+	assert!(!spm.is_reachable(5.0, 5.0));
+	
+	cells[9] = false;
+	let spm = JkmShortestPathMap::from_occupancy_grid(&cells, 4, 10.0, (5.0, 5.0), (35.0, 35.0));
+	check_module_invariants(&spm);
+	assert_eq!(spm.graph[spm.start_point_index].cost, 60.0);
+	
+	let spm = JkmShortestPathMap::<i32>::from_occupancy_grid(&cells, 4, 10, (5, 5), (35, 35));
+	check_module_invariants(&spm);
+	assert_eq!(spm.graph[spm.start_point_index].cost, 60);
+	
+	// a wall from border to border, the rectangles do not stick out further than insert_obstacle() cuts them
+	let wall = [false, false, false, true, true, true, false, false, false];
+	let spm = JkmShortestPathMap::from_occupancy_grid(&wall, 3, 10.0, (5.0, 5.0), (25.0, 25.0));
+	check_module_invariants(&spm);
+	assert!(!spm.is_reachable(5.0, 5.0));
+	let margin = 4.0 * <f64 as Scalar>::EPS;
+	assert!(spm.obstacles.contains(&(-margin, 10.0, 30.0 + 2.0 * margin, 10.0)));
+}
+
+#[test]
+fn closed_touch_policy_seals_touching_obstacles() {
+	// one obstacle below the other and one that only meets it at a corner
+	for &(lower, seal) in [((40.0, 50.0, 10.0, 60.0), (45.0, 50.0)), ((50.0, 50.0, 10.0, 60.0), (50.0, 50.0))].iter() {
+		let mut spm = JkmShortestPathMap::new((10.0, 20.0), (90.0, 80.0), (0.0, 0.0, 100.0, 100.0));
+		spm.add_map_border();
+		spm.insert_obstacle(40.0, -10.0, 10.0, 60.0);
+		spm.insert_obstacle(lower.0, lower.1, lower.2, lower.3);
+		spm.insert_obstacle(80.0, 5.0, 5.0, 5.0);
+		check_module_invariants(&spm);
+		assert_eq!(spm.graph[spm.start_point_index].cost, 140.0);
+		assert!(!spm.coordinate_is_blocked(seal.0, seal.1));
+		assert!(spm.seams.involve(lower) && !spm.seams.involve((80.0, 5.0, 5.0, 5.0)));
+		
+		spm.set_touch_policy(TouchPolicy::Closed);
+		check_module_invariants(&spm);
+		assert!(!spm.is_reachable(10.0, 20.0));
+		let token = spm.checkpoint();
+		spm.remove_obstacle(lower.0, lower.1, lower.2, lower.3);
+		assert!(!spm.seams.involve(lower));
+		spm.rollback(token);
+		assert!(spm.seams.involve(lower));
+		assert!(!spm.is_reachable(10.0, 20.0));
+		assert!(spm.coordinate_is_blocked(seal.0, seal.1));
+		assert!(spm.is_reachable(70.0, 50.0));
+		assert_eq!(spm.nearest_checkpoint(20.0, 50.0), None);
+		
+		spm.set_touch_policy(TouchPolicy::Open);
+		check_module_invariants(&spm);
+		assert_eq!(spm.graph[spm.start_point_index].cost, 140.0);
+	}
+	
+	// inserted after the policy has been chosen
+	let mut spm = JkmShortestPathMap::new((10.0, 20.0), (90.0, 80.0), (0.0, 0.0, 100.0, 100.0));
+	spm.set_touch_policy(TouchPolicy::Closed);
+	spm.add_map_border();
+	spm.insert_obstacle(40.0, -10.0, 10.0, 60.0);
+	spm.insert_obstacle(50.0, 50.0, 10.0, 60.0);
+	check_module_invariants(&spm);
+	assert!(!spm.is_reachable(10.0, 20.0));
+	spm.remove_obstacle(50.0, 50.0, 10.0, 60.0);
+	check_module_invariants(&spm);
+	assert_eq!(spm.graph[spm.start_point_index].cost, 140.0);
+}
+
+#[test]
+fn min_clearance_avoids_narrow_gaps() {
+	let mut spm = JkmShortestPathMap::new((10.0, 50.0), (90.0, 60.0), (0.0, 0.0, 100.0, 100.0));
+	spm.add_map_border();
+	// a gap of 2 in the middle of the wall and one of 20 at the bottom of the map
+	spm.insert_obstacle(40.0, -10.0, 10.0, 59.0);
+	spm.insert_obstacle(40.0, 51.0, 10.0, 29.0);
+	check_module_invariants(&spm);
+	assert_eq!(spm.graph[spm.start_point_index].cost, 90.0);
+	
+	assert_eq!(spm.path_from_with_min_clearance(10.0, 50.0, 2.0).unwrap().cost, 90.0);
+	let path = spm.path_from_with_min_clearance(10.0, 50.0, 5.0).unwrap();
+	assert_eq!(path.cost, 130.0);
+	assert_eq!(path.points.first(), Some(&(10.0, 50.0)));
+	assert_eq!(path.points.last(), Some(&(90.0, 60.0)));
+	assert_eq!(spm.path_from_with_min_clearance(10.0, 50.0, 30.0), None);
+	
+	spm.remove_obstacle(40.0, 51.0, 10.0, 29.0);
+	check_module_invariants(&spm);
+	assert_eq!(spm.path_from_with_min_clearance(10.0, 50.0, 30.0).unwrap().cost, 90.0);
+	
+	// with stored clearance, the results stay the same
+	spm.track_clearance();
+	check_module_invariants(&spm);
+	assert_eq!(spm.path_from_with_min_clearance(10.0, 50.0, 30.0).unwrap().cost, 90.0);
+	spm.insert_obstacle(40.0, 51.0, 10.0, 29.0);
+	check_module_invariants(&spm);
+	assert_eq!(spm.path_from_with_min_clearance(10.0, 50.0, 5.0).unwrap().cost, 130.0);
+	assert_eq!(spm.path_from_with_min_clearance(10.0, 50.0, 30.0), None);
+	
+	// the border of the map limits the clearance even if it is open
+	let spm = JkmShortestPathMap::new((10.0, 50.0), (90.0, 50.0), (0.0, 0.0, 100.0, 100.0));
+	assert_eq!(spm.path_from_with_min_clearance(10.0, 50.0, 100.0).unwrap().cost, 80.0);
+	assert_eq!(spm.path_from_with_min_clearance(10.0, 50.0, 101.0), None);
+}
+
+#[test]
+fn path_changes_report_rerouted_nodes() {
+	let mut spm = JkmShortestPathMap::new((10.0, 20.0), (190.0, 50.0), (0.0, 0.0, 200.0, 100.0));
+	spm.add_map_border();
+	assert_eq!(spm.take_path_changes(), None);
+	spm.track_path_changes();
+	assert!(spm.take_path_changes().unwrap().is_empty());
+	
+	spm.insert_obstacle(100.0, -10.0, 20.0, 100.0);
+	check_module_invariants(&spm);
+	let changes = spm.take_path_changes().unwrap();
+	assert!(!changes.is_empty());
+	assert!(changes.nodes.contains(&(10.0, 20.0)));
+	assert!(!changes.nodes.contains(&(190.0, 50.0)));
+	assert!(changes.affects(10.0, 20.0));
+	assert!(spm.take_path_changes().unwrap().is_empty());
+	
+	// an obstacle next to the paths changes nothing but the nodes around it
+	spm.insert_obstacle(150.0, 10.0, 10.0, 10.0);
+	let changes = spm.take_path_changes().unwrap();
+	assert!(!changes.nodes.contains(&(10.0, 20.0)));
+	assert!(!changes.affects(10.0, 20.0));
+	
+	// rolling back changes the paths that the obstacle changed
+	let token = spm.checkpoint();
+	spm.insert_obstacle(40.0, 10.0, 20.0, 20.0);
+	spm.take_path_changes();
+	spm.rollback(token);
+	assert!(spm.take_path_changes().unwrap().nodes.contains(&(10.0, 20.0)));
+	spm.untrack_path_changes();
+	assert_eq!(spm.take_path_changes(), None);
+}
+
+#[test]
+fn path_tokens_notice_obstacles_on_the_path() {
+	let mut spm = JkmShortestPathMap::new((10.0, 20.0), (190.0, 50.0), (0.0, 0.0, 200.0, 100.0));
+	spm.add_map_border();
+	let path = spm.find_path((10.0, 20.0), (190.0, 50.0)).unwrap();
+	let token = spm.path_token(&path.points);
+	let (checkpoint, next) = spm.next_checkpoint_with_token(10.0, 20.0).unwrap();
+	assert_eq!(spm.next_checkpoint(10.0, 20.0), Some(checkpoint));
+	assert!(spm.is_still_valid(&token) && spm.is_still_valid(&next));
+	
+	// far away from both paths
+	spm.insert_obstacle(150.0, 70.0, 10.0, 10.0);
+	assert!(spm.is_still_valid(&token) && spm.is_still_valid(&next));
+	
+	let state = spm.checkpoint();
+	spm.insert_obstacle(100.0, -10.0, 20.0, 100.0);
+	assert!(!spm.is_still_valid(&token));
+	let token = spm.path_token(&spm.find_path((10.0, 20.0), (190.0, 50.0)).unwrap().points);
+	assert!(spm.is_still_valid(&token));
+	spm.rollback(state);
+	assert!(!spm.is_still_valid(&token));
+	
+	// a directional zone on the path changes it just like an obstacle
+	let mut spm = JkmShortestPathMap::new((50.0, 90.0), (50.0, 10.0), (0.0, 0.0, 100.0, 100.0));
+	let (checkpoint, token) = spm.next_checkpoint_with_token(50.0, 90.0).unwrap();
+	assert_eq!(checkpoint, (50.0, 10.0));
+	spm.insert_directional_zone(0.0, 40.0, 100.0, 20.0, Direction::South);
+	assert!(spm.next_checkpoint(50.0, 90.0) != Some(checkpoint));
+	assert!(!spm.is_still_valid(&token));
+}
+
+#[test]
+fn stats_count_graph_and_last_update() {
+	let mut spm = JkmShortestPathMap::new((10.0, 20.0), (190.0, 50.0), (0.0, 0.0, 200.0, 100.0));
+	spm.add_map_border();
+	spm.insert_obstacle(100.0, 20.0, 10.0, 10.0);
+	assert_eq!(spm.stats().last_update, UpdateStats::default());
+	spm.remove_obstacle(100.0, 20.0, 10.0, 10.0);
+	
+	spm.enable_stats();
+	spm.insert_obstacle(100.0, -10.0, 20.0, 100.0);
+	let stats = spm.stats();
+	assert_eq!(stats.obstacles, 5);
+	assert_eq!(stats.live_nodes + stats.dead_nodes, stats.nodes);
+	assert_eq!(stats.edges, spm.graph.iter().flat_map(|node| node.neighbours.iter()).filter(|n| n.is_some()).count() / 2);
+	assert!(stats.last_update.invalidated > 0 && stats.last_update.relaxed > 0);
+	assert!(stats.last_update.max_update_depth >= 1 && stats.last_update.max_invalidation_depth >= 1);
+	
+	spm.remove_obstacle(100.0, -10.0, 20.0, 100.0);
+	assert_eq!(spm.stats().obstacles, 4);
+	assert_eq!(spm.stats().last_update.insert_phases, InsertPhases::default());
+	
+	spm.disable_stats();
+	spm.insert_obstacle(100.0, -10.0, 20.0, 100.0);
+	assert_eq!(spm.stats().last_update, UpdateStats::default());
+}
+
+#[test]
+fn jkmmap_round_trip_keeps_costs() {
+	let mut spm = JkmShortestPathMap::new((10.0, 20.0), (190.0, 60.0), (0.0, 0.0, 200.0, 100.0));
+	spm.add_map_border();
+	spm.insert_obstacle(90.0, 0.0, 20.0, 80.0);
+	assert_eq!(spm.check_invariants(), Ok(()));
+	let mut file = Vec::new();
+	spm.write_jkmmap(&mut file).unwrap();
+
+	let read = JkmShortestPathMap::read_jkmmap(&file[..], (10.0, 20.0)).unwrap();
+	assert_eq!(read.check_invariants(), Ok(()));
+	assert_eq!(read.obstacles.len(), spm.obstacles.len());
+	assert_eq!(read.get_destination_coordinates(), (190.0, 60.0));
+	assert_eq!(read.nearest_checkpoint(10.0, 20.0), spm.nearest_checkpoint(10.0, 20.0));
+	assert_eq!(read.graph[read.start_point_index].cost, spm.graph[spm.start_point_index].cost);
+
+	spm.graph[spm.start_point_index].cost = 1.0;
+	assert!(spm.check_invariants().is_err());
+	assert!(JkmShortestPathMap::<f64>::read_jkmmap(&b"1|2|3\n"[..], (10.0, 20.0)).is_err());
+}
+
 // TODO List
 	//	Invariant-styled:
-		// There is no shorter path in the graph
 		// There is no shorter path OUTSIDE of the nodes of the graph
\ No newline at end of file
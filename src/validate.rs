@@ -0,0 +1,153 @@
+//! Consistency checks of the graph that can be run outside of the unit tests, e.g. by the command line tool
+
+use std::cmp::Ordering;
+use std::collections::HashSet;
+
+use super::{JkmShortestPathMap, Scalar, NORTH, EAST, SOUTH, WEST, PORTAL};
+use regions::{DESTINATION_REGION, NO_REGION};
+
+impl<S: Scalar> JkmShortestPathMap<S> {
+
+	/// Checks that the graph is consistent: edges are stored on both nodes and connect aligned nodes, every shortest path
+	/// leads to the destination without a cycle, the costs add up along the paths, no path goes against a directional zone,
+	/// no edge crosses an obstacle and every path is as short as the one Dijkstra's algorithm finds in the graph.
+	/// No two nodes share a coordinate and only the start, the destination, portal ends and nodes inside of an obstacle
+	/// or on its border are without edges, the latter are kept for when the obstacle is disabled or removed.
+	/// The regions are the connected parts of the graph, with DESTINATION_REGION being the nodes that have a path.
+	/// Returns a description of the first violation that is found. This is slow, it is meant for debugging.
+	pub fn check_invariants(&self) -> Result<(), String> {
+		self.check_graph()?;
+		self.check_regions()
+	}
+
+	// Everything check_invariants() looks at besides the regions, which are only labeled at the end of an update
+	pub(crate) fn check_graph(&self) -> Result<(), String> {
+		for (i, node) in self.graph.iter().enumerate() {
+			if node.x == S::NEG_INFINITY { continue; }
+			if node.neighbours == [None; 4] && !self.may_be_without_edges(i) {
+				return Err(format!("Node #{} at ({}, {}) has no edges.", i, node.x, node.y));
+			}
+			for direction in 0..4 {
+				let neighbour = match node.neighbours[direction] {
+					Some(neighbour) => neighbour,
+					None => continue,
+				};
+				if neighbour >= self.graph.len() {
+					return Err(format!("Node #{} has a neighbour #{} that does not exist.", i, neighbour));
+				}
+				let other = &self.graph[neighbour];
+				if other.neighbours[(direction + 2) % 4] != Some(i) {
+					return Err(format!("Node #{} has the neighbour #{} in direction {}, but not the other way around.", i, neighbour, direction));
+				}
+				let aligned = match direction {
+					NORTH => other.x == node.x && other.y < node.y,
+					EAST => other.y == node.y && other.x > node.x,
+					SOUTH => other.x == node.x && other.y > node.y,
+					WEST => other.y == node.y && other.x < node.x,
+					_ => unreachable!(),
+				};
+				if !aligned {
+					return Err(format!("Node #{} at ({}, {}) and its neighbour #{} at ({}, {}) are not aligned in direction {}.", i, node.x, node.y, neighbour, other.x, other.y, direction));
+				}
+				let free = match direction {
+					NORTH => self.v_line_overlaps_no_obstacle(node.x, other.y, node.y),
+					EAST => self.h_line_overlaps_no_obstacle(node.x, node.y, other.x),
+					_ => true,
+				};
+				if !free {
+					return Err(format!("The edge from node #{} at ({}, {}) to node #{} at ({}, {}) crosses an obstacle.", i, node.x, node.y, neighbour, other.x, other.y));
+				}
+			}
+
+			if let Some(next) = self.next_node(i) {
+				let step = match node.portal {
+					Some(end) if node.shortest_path == Some(PORTAL) => end.cost,
+					_ => self.distance_on_map(i, next),
+				};
+				if node.cost != self.graph[next].cost + step {
+					return Err(format!("Node #{} has the cost {}, but the next node on its path #{} adds up to {}.", i, node.cost, next, self.graph[next].cost + step));
+				}
+			}
+			else if node.shortest_path.is_some() {
+				return Err(format!("The shortest path of node #{} leads to a node that does not exist.", i));
+			}
+			if let Some(direction) = node.shortest_path.filter(|&sp| sp != PORTAL) {
+				if !self.can_move(i, direction) {
+					return Err(format!("The shortest path of node #{} goes in direction {} through a directional zone that does not allow it.", i, direction));
+				}
+			}
+
+			let mut n = i;
+			let mut steps = 0;
+			while let Some(next) = self.next_node(n) {
+				if steps > self.graph.len() {
+					return Err(format!("The shortest path of node #{} runs in a cycle.", i));
+				}
+				n = next;
+				steps += 1;
+			}
+			if node.shortest_path.is_some() && n != self.end_point_index {
+				return Err(format!("The shortest path of node #{} ends on node #{} instead of the destination.", i, n));
+			}
+		}
+
+		let mut live: Vec<usize> = (0..self.graph.len()).filter(|&i| self.graph[i].x != S::NEG_INFINITY).collect();
+		live.sort_by(|&a, &b| {
+			let (a, b) = (&self.graph[a], &self.graph[b]);
+			a.x.partial_cmp(&b.x).unwrap_or(Ordering::Equal).then(a.y.partial_cmp(&b.y).unwrap_or(Ordering::Equal))
+		});
+		for pair in live.windows(2) {
+			let (a, b) = (&self.graph[pair[0]], &self.graph[pair[1]]);
+			if a.x == b.x && a.y == b.y {
+				return Err(format!("Node #{} and node #{} are both at ({}, {}).", pair[0], pair[1], a.x, a.y));
+			}
+		}
+
+		let (costs, _) = self.dijkstra();
+		for (i, node) in self.graph.iter().enumerate() {
+			if node.x != S::NEG_INFINITY && node.cost != costs[i] {
+				return Err(format!("Node #{} has the cost {}, but the shortest path in the graph costs {}.", i, node.cost, costs[i]));
+			}
+		}
+		Ok(())
+	}
+
+	// Nodes with a path lie in DESTINATION_REGION, the nodes that are cut off share a region exactly with the nodes they are connected to
+	pub(crate) fn check_regions(&self) -> Result<(), String> {
+		let mut seen = vec![false; self.graph.len()];
+		let mut used = HashSet::new();
+		for (i, node) in self.graph.iter().enumerate() {
+			if node.x == S::NEG_INFINITY || seen[i] { continue; }
+			if node.cost < S::INFINITY {
+				if node.region != DESTINATION_REGION {
+					return Err(format!("Node #{} has a path, but lies in region {}.", i, node.region));
+				}
+				continue;
+			}
+			let region = node.region;
+			if region == DESTINATION_REGION || region == NO_REGION || !used.insert(region) {
+				return Err(format!("Node #{} is cut off, but lies in region {} which is not its own.", i, region));
+			}
+			let mut stack = vec![i];
+			seen[i] = true;
+			while let Some(n) = stack.pop() {
+				if self.graph[n].region != region {
+					return Err(format!("Node #{} is connected to node #{}, but lies in region {} instead of {}.", n, i, self.graph[n].region, region));
+				}
+				let portal = self.graph[n].portal.map(|end| end.other);
+				for &m in self.graph[n].neighbours.iter().chain(Some(&portal)).filter_map(|m| m.as_ref()) {
+					if !seen[m] && self.graph[m].cost == S::INFINITY { seen[m] = true; stack.push(m); }
+				}
+			}
+		}
+		Ok(())
+	}
+
+	// Whether the node is kept in the graph even if it has no edges, like the nodes inside of an enabled managed obstacle,
+	// a closed border or on a sealed border with TouchPolicy::Closed
+	fn may_be_without_edges(&self, n: usize) -> bool {
+		let (x, y) = (self.graph[n].x, self.graph[n].y);
+		n == self.start_point_index || n == self.end_point_index || self.graph[n].portal.is_some()
+			|| self.obstacles.iter().any(|&(ox, oy, ow, oh)| x >= ox && x <= ox + ow && y >= oy && y <= oy + oh)
+	}
+}
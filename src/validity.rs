@@ -0,0 +1,81 @@
+//! Tokens that tell whether a cached path is still valid, i.e. no obstacle or zone has been inserted or removed on it since it was planned
+
+use super::{JkmShortestPathMap, Scalar};
+
+// Older changes are forgotten, tokens from before them are no longer valid
+const MAX_LOGGED_CHANGES: usize = 4096;
+
+/// Remembers a path together with the version of the map it was planned on, see JkmShortestPathMap::is_still_valid()
+#[derive(Clone, PartialEq, Debug)]
+pub struct PathToken<S = f64> {
+	version: usize,
+	points: Vec<(S, S)>,
+}
+
+// Areas of all obstacles and directional zones that were inserted or removed and the ends of portals that were added or detached,
+// the version of the map is the number of changes so far
+#[derive(Clone, Debug)]
+pub(crate) struct ChangeLog<S> {
+	areas: Vec<(S,S,S,S)>,
+	// version before the first logged change
+	start: usize,
+}
+
+impl<S: Scalar> ChangeLog<S> {
+	pub(crate) fn new() -> ChangeLog<S> {
+		ChangeLog { areas: Vec::new(), start: 0 }
+	}
+
+	pub(crate) fn record(&mut self, area: (S,S,S,S)) {
+		if self.areas.len() == MAX_LOGGED_CHANGES {
+			self.areas.drain(..MAX_LOGGED_CHANGES / 2);
+			self.start += MAX_LOGGED_CHANGES / 2;
+		}
+		self.areas.push(area);
+	}
+
+	// Makes all tokens invalid, used for changes that are not logged
+	pub(crate) fn forget(&mut self) {
+		self.start += self.areas.len() + 1;
+		self.areas.clear();
+	}
+
+	fn version(&self) -> usize {
+		self.start + self.areas.len()
+	}
+}
+
+impl<S: Scalar> JkmShortestPathMap<S> {
+
+	/// Creates a token for a path given by its checkpoints, e.g. the points of a Path or a position together with its next checkpoint.
+	pub fn path_token(&self, points: &[(S, S)]) -> PathToken<S> {
+		PathToken { version: self.change_log.version(), points: points.to_vec() }
+	}
+
+	/// Same as next_checkpoint(), together with a token for the line from the coordinate to the checkpoint
+	pub fn next_checkpoint_with_token(&self, x: S, y: S) -> Option<((S,S), PathToken<S>)> {
+		self.next_checkpoint(x, y).map(|checkpoint| (checkpoint, self.path_token(&[(x, y), checkpoint])))
+	}
+
+	/// Checks whether an obstacle or a directional zone has been inserted or removed on the path of the token, including its border,
+	/// or a portal end has been added or detached on it since the token was created.
+	/// Only the lines between aligned checkpoints are checked, jumps through portals are not. Rolling back to a checkpoint
+	/// invalidates all tokens, so does a long history of changes since the token was created.
+	/// A valid path is not necessarily still the shortest one.
+	pub fn is_still_valid(&self, token: &PathToken<S>) -> bool {
+		if token.version < self.change_log.start || token.version > self.change_log.version() { return false; }
+		let changes = &self.change_log.areas[token.version - self.change_log.start..];
+		if token.points.len() == 1 {
+			let (x, y) = token.points[0];
+			return !changes.iter().any(|&area| line_touches_area((x, y), (x, y), area));
+		}
+		!token.points.windows(2)
+			.filter(|line| line[0].0 == line[1].0 || line[0].1 == line[1].1)
+			.any(|line| changes.iter().any(|&area| line_touches_area(line[0], line[1], area)))
+	}
+}
+
+// Whether an aligned line and a rectangle have at least one point in common
+fn line_touches_area<S: Scalar>(a: (S, S), b: (S, S), (x, y, w, h): (S,S,S,S)) -> bool {
+	a.0.min(b.0) <= x + w && a.0.max(b.0) >= x && a.1.min(b.1) <= y + h && a.1.max(b.1) >= y
+}
@@ -0,0 +1,123 @@
+//! Areas of the map that can only be crossed in one direction, like conveyor belts or one-way gates
+
+use super::{JkmShortestPathMap, Scalar, NORTH, EAST, SOUTH, WEST, h_line_overlaps_obstacle, v_line_overlaps_obstacle};
+
+/// One of the four directions in which a path can go on the map
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Direction {
+	North,
+	East,
+	South,
+	West,
+}
+
+impl Direction {
+	fn index(self) -> usize {
+		match self {
+			Direction::North => NORTH,
+			Direction::East => EAST,
+			Direction::South => SOUTH,
+			Direction::West => WEST,
+		}
+	}
+}
+
+/// Area in which paths must not go against the allowed direction
+#[derive(Copy, Clone, Debug)]
+pub(crate) struct DirectionalZone<S> {
+	area: (S,S,S,S),
+	allowed: usize,
+}
+
+impl<S: Copy> DirectionalZone<S> {
+	pub(crate) fn area(&self) -> (S,S,S,S) {
+		self.area
+	}
+}
+
+impl<S: Scalar> JkmShortestPathMap<S> {
+
+	/// Adds a zone (x, y, w, h) in which paths can not go in the direction opposite to the allowed one.
+	/// Paths can still cross the zone perpendicularly and paths along its border are not affected.
+	/// The shortest paths that go against the allowed direction are computed again afterwards.
+	pub fn insert_directional_zone(&mut self, x: S, y: S, w: S, h: S, allowed: Direction) {
+		self.zones.push(DirectionalZone { area: (x,y,w,h), allowed: allowed.index() });
+		self.change_log.record((x,y,w,h));
+		self.mark_clusters((x,y,w,h));
+		// paths that follow an edge into the zone have to be rerouted before the edge is split,
+		// otherwise the new node on the border could lead back to where the path came from
+		let all: Vec<usize> = (0..self.graph.len()).collect();
+		self.reroute_against_zones(&all);
+		self.split_at_zones(&all);
+		self.after_update();
+	}
+
+	// Checks whether a path may go from the node to its neighbour in the given direction.
+	// Edges are always stored in both nodes, even if they can only be used in one direction.
+	pub(crate) fn can_move(&self, n: usize, direction: usize) -> bool {
+		if self.zones.is_empty() { return true; }
+		match self.graph[n].neighbours[direction] {
+			Some(neighbour) => move_is_allowed(&self.zones, (self.graph[n].x, self.graph[n].y), (self.graph[neighbour].x, self.graph[neighbour].y)),
+			None => false,
+		}
+	}
+
+	// Splits the edges of the given nodes that cross the border of a zone, so that every edge is either completely inside or outside of it.
+	pub(crate) fn split_at_zones(&mut self, nodes: &[usize]) {
+		let mut queue = nodes.to_vec();
+		while let Some(n) = queue.pop() {
+			if n >= self.graph.len() || self.graph[n].x == S::NEG_INFINITY { continue; }
+			for direction in 0..4 {
+				let neighbour = match self.graph[n].neighbours[direction] {
+					Some(neighbour) => neighbour,
+					None => continue,
+				};
+				// each edge is split from its left or upper node
+				let (first, direction, second) = if direction == EAST || direction == SOUTH { (n, direction, neighbour) } else { (neighbour, (direction + 2) % 4, n) };
+				let cut = self.zones.iter().filter_map(|zone| {
+					let (x, y, w, h) = zone.area;
+					let (node, other) = (&self.graph[first], &self.graph[second]);
+					if direction == EAST && node.y > y && node.y < y + h {
+						[x, x + w].iter().cloned().find(|&cut| node.x < cut && other.x > cut)
+					}
+					else if direction == SOUTH && node.x > x && node.x < x + w {
+						[y, y + h].iter().cloned().find(|&cut| node.y < cut && other.y > cut)
+					}
+					else { None }
+				}).next();
+				if let Some(cut) = cut {
+					let new_index = self.split_edge(first, direction, cut);
+					self.update_neighbours(new_index);
+					// both parts and the other edges of the node can still cross the border of a zone
+					queue.push(n);
+					queue.push(new_index);
+					break;
+				}
+			}
+		}
+	}
+
+	// Invalidates the paths of the given nodes that go against the allowed direction of a zone and computes them again
+	pub(crate) fn reroute_against_zones(&mut self, nodes: &[usize]) {
+		for &n in nodes.iter() {
+			if n >= self.graph.len() { continue; }
+			match self.graph[n].shortest_path {
+				Some(direction) if direction < 4 && !self.can_move(n, direction) => self.invalidate_paths_through_node(n),
+				_ => {}
+			}
+		}
+		self.update();
+	}
+}
+
+// Checks whether a path may go straight from one coordinate to the other, the coordinates have to be aligned
+pub(crate) fn move_is_allowed<S: Scalar>(zones: &[DirectionalZone<S>], from: (S, S), to: (S, S)) -> bool {
+	let direction = if from.1 == to.1 {
+		if to.0 > from.0 { EAST } else if to.0 < from.0 { WEST } else { return true; }
+	}
+	else if to.1 > from.1 { SOUTH } else { NORTH };
+	!zones.iter().any(|zone| (zone.allowed + 2) % 4 == direction &&
+		if from.1 == to.1 { h_line_overlaps_obstacle( (from.0.min(to.0), from.1, from.0.max(to.0)), zone.area ) }
+		else { v_line_overlaps_obstacle( (from.0, from.1.min(to.1), from.1.max(to.1)), zone.area ) }
+	)
+}